@@ -10,10 +10,14 @@ fn main() {
 
 #[cfg(target_os = "linux")]
 mod example {
-    use headcrab::target::{LinuxTarget, UnixTarget};
+    use headcrab::{
+        symbol::Dwarf,
+        target::{DebugEvent, LaunchOptions, LinuxTarget, UnixTarget},
+    };
 
     struct Context {
         remote: Option<LinuxTarget>,
+        debuginfo: Option<Dwarf>,
     }
 
     impl Context {
@@ -24,13 +28,42 @@ mod example {
                 Err("No running process".to_string().into())
             }
         }
+
+        fn remote_mut(&mut self) -> Result<&mut LinuxTarget, Box<dyn std::error::Error>> {
+            if let Some(remote) = &mut self.remote {
+                Ok(remote)
+            } else {
+                Err("No running process".to_string().into())
+            }
+        }
+
+        fn load_debuginfo(&mut self, path: &str) {
+            self.debuginfo = match Dwarf::new(path) {
+                Ok(debuginfo) => Some(debuginfo),
+                Err(err) => {
+                    println!("\x1b[93mFailed to load debug info: {}\x1b[0m", err);
+                    None
+                }
+            };
+        }
+
+        /// Prints a debug event, forgetting about the debuggee if it has terminated.
+        fn handle_event(&mut self, event: DebugEvent) {
+            println!("{:?}", event);
+            if let DebugEvent::Exited(_) | DebugEvent::Killed(_) = event {
+                self.remote = None;
+            }
+        }
     }
 
     pub fn main() {
         let mut rl = rustyline::Editor::<()>::with_config(
             rustyline::Config::builder().auto_add_history(true).build(),
         );
-        let mut context = Context { remote: None };
+        let mut context = Context {
+            remote: None,
+            debuginfo: None,
+        };
 
         let mut cmds = vec![];
         let mut exec_cmd = None;
@@ -50,19 +83,15 @@ mod example {
                     format!("Found argument '{}' which wasn't expected", arg)
                 }
                 _ => {
-                    if args.next().is_none() {
-                        exec_cmd = Some(arg);
-                        break;
-                    } else {
-                        "Debuggee arguments are not yet supported".to_string()
-                    }
+                    exec_cmd = Some((arg, args.collect::<Vec<_>>()));
+                    break;
                 }
             };
             println!(
                 "error: {}
 
     USAGE:
-        {} [OPTIONS] executable-file
+        {} [OPTIONS] executable-file [ARGS]...
 
     OPTIONS:
         -ex <COMMAND>           Run command on startup",
@@ -71,15 +100,18 @@ mod example {
             std::process::exit(1);
         }
 
-        if let Some(exec_cmd) = exec_cmd {
-            println!("Starting program: {}", exec_cmd);
-            context.remote = Some(match LinuxTarget::launch(&exec_cmd) {
+        if let Some((exec_cmd, exec_args)) = exec_cmd {
+            let command: Vec<_> = std::iter::once(&exec_cmd).chain(&exec_args).collect();
+            println!("Starting program: {:?}", command);
+            let options = LaunchOptions::new(&exec_cmd).args(exec_args);
+            context.remote = Some(match LinuxTarget::launch(options) {
                 Ok(target) => target,
                 Err(err) => {
                     println!("\x1b[91mError while launching debuggee: {}\x1b[0m", err);
                     std::process::exit(1);
                 }
             });
+            context.load_debuginfo(&exec_cmd);
         }
 
         for command in cmds.into_iter() {
@@ -125,7 +157,9 @@ mod example {
             Some("exec") => {
                 if let Some(cmd) = parts.next() {
                     println!("Starting program: {}", cmd);
-                    context.remote = Some(LinuxTarget::launch(cmd)?);
+                    let options = LaunchOptions::new(cmd).args(parts.filter(|arg| !arg.is_empty()));
+                    context.remote = Some(LinuxTarget::launch(options)?);
+                    context.load_debuginfo(cmd);
                 }
             }
             Some("attach") => {
@@ -133,15 +167,68 @@ mod example {
                     let pid = nix::unistd::Pid::from_raw(pid.parse()?);
                     println!("Attaching to process {}", pid);
                     context.remote = Some(LinuxTarget::attach(pid)?);
+                    context.load_debuginfo(&format!("/proc/{}/exe", pid));
+                }
+            }
+            Some("detach") => {
+                let remote = context.remote.take().ok_or("No running process")?;
+                remote.detach()?;
+                println!("Detached");
+            }
+            Some("kill") => {
+                let remote = context.remote.take().ok_or("No running process")?;
+                println!("{:?}", remote.kill()?);
+            }
+            Some("cont") | Some("continue") => {
+                context.remote_mut()?.unpause()?;
+                let event = context.remote_mut()?.next_event()?;
+                context.handle_event(event);
+            }
+            Some("s") | Some("step") => {
+                let remote = context.remote.as_mut().ok_or("No running process")?;
+                let debuginfo = context.debuginfo.as_ref().ok_or("No debug info loaded")?;
+                let event = remote.step_line(debuginfo)?;
+                context.handle_event(event);
+            }
+            Some("n") | Some("next") => {
+                let remote = context.remote.as_mut().ok_or("No running process")?;
+                let debuginfo = context.debuginfo.as_ref().ok_or("No debug info loaded")?;
+                let event = remote.next_line(debuginfo)?;
+                context.handle_event(event);
+            }
+            Some("bt") | Some("backtrace") => {
+                let remote = context.remote()?;
+                let debuginfo = context.debuginfo.as_ref().ok_or("No debug info loaded")?;
+                for (index, frame) in remote.unwind(debuginfo)?.iter().enumerate() {
+                    let functions = frame.symbolicate(debuginfo, index == 0)?;
+                    if functions.is_empty() {
+                        let name = debuginfo.get_address_symbol(frame.lookup_addr(index == 0));
+                        println!(
+                            "#{} {:#016x} in {}",
+                            index,
+                            frame.pc,
+                            name.as_deref().unwrap_or("??")
+                        );
+                    }
+                    for function in functions {
+                        let name = function.demangled_name.or(function.name);
+                        print!(
+                            "#{} {:#016x} in {}",
+                            index,
+                            frame.pc,
+                            name.as_deref().unwrap_or("??")
+                        );
+                        match function.location {
+                            Some(location) => println!(" at {}:{}", location.file, location.line),
+                            None => println!(),
+                        }
+                    }
                 }
             }
-            Some("cont") | Some("continue") => context.remote()?.unpause()?,
             Some("regs") => match parts.next() {
                 Some("read") => println!("{:?}", context.remote()?.read_regs()?),
                 Some(sub) => Err(format!("Unknown `regs` subcommand `{}`", sub))?,
-                None => Err(format!(
-                    "Expected subcommand found nothing. Try `regs read`"
-                ))?,
+                None => Err("Expected subcommand found nothing. Try `regs read`".to_string())?,
             },
             Some("") | None => {}
             Some(command) => Err(format!("Unknown command `{}`", command))?,
@@ -0,0 +1,72 @@
+// Demangling of symbol names, so that functions can be referred to by their paths in the source
+// code instead of their symbol names.
+
+/// Demangles a symbol name mangled with either of the Rust mangling schemes (legacy or v0), or
+/// with the Itanium C++ scheme.
+/// The hash suffix of Rust names is omitted, e.g. `_ZN5hello4main17h1c6a1d6e8a8b9d6bE` becomes
+/// `hello::main`. Returns `None` if the name is not mangled.
+pub fn demangle(name: &str) -> Option<String> {
+    demangle_impl(name, false)
+}
+
+/// Demangles a symbol name like `demangle`, but keeps the hash suffix of Rust names, e.g.
+/// `hello::main::h1c6a1d6e8a8b9d6b`.
+pub(super) fn demangle_with_hash(name: &str) -> Option<String> {
+    demangle_impl(name, true)
+}
+
+fn demangle_impl(name: &str, with_hash: bool) -> Option<String> {
+    if let Ok(demangled) = rustc_demangle::try_demangle(name) {
+        return Some(if with_hash {
+            demangled.to_string()
+        } else {
+            format!("{:#}", demangled)
+        });
+    }
+
+    // Only names with the `_Z` prefix are mangled, the parser accepts plain type names as well.
+    if !name.starts_with("_Z") {
+        return None;
+    }
+    cpp_demangle::Symbol::new(name)
+        .ok()?
+        .demangle(&cpp_demangle::DemangleOptions::default())
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{demangle, demangle_with_hash};
+
+    #[test]
+    fn rust_legacy() {
+        let name = "_ZN5hello4main17h1c6a1d6e8a8b9d6bE";
+        assert_eq!(demangle(name).as_deref(), Some("hello::main"));
+        assert_eq!(
+            demangle_with_hash(name).as_deref(),
+            Some("hello::main::h1c6a1d6e8a8b9d6b")
+        );
+    }
+
+    #[test]
+    fn rust_v0() {
+        let name = "_RNvCs1234_5hello4main";
+        assert_eq!(demangle(name).as_deref(), Some("hello::main"));
+        // The v0 scheme has no hash suffix, but a disambiguator for each crate.
+        assert_eq!(
+            demangle_with_hash(name).as_deref(),
+            Some("hello[3c1c0]::main")
+        );
+    }
+
+    #[test]
+    fn cpp() {
+        assert_eq!(demangle("_ZN3foo3barEi").as_deref(), Some("foo::bar(int)"));
+    }
+
+    #[test]
+    fn not_mangled() {
+        assert_eq!(demangle("main"), None);
+        assert_eq!(demangle("a_function"), None);
+    }
+}
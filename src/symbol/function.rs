@@ -0,0 +1,254 @@
+// Functions of the debuggee, read from the `DW_TAG_subprogram` entries in `.debug_info`.
+
+use gimli::Reader as _;
+use std::ops::Range;
+
+use super::{demangle, line, AttributeValue, Reader};
+
+/// A function described by the debug info.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Function {
+    name: Option<String>,
+    linkage_name: Option<String>,
+    demangled_name: Option<String>,
+    ranges: Vec<Range<usize>>,
+    decl_file: Option<String>,
+    decl_line: Option<u64>,
+    unit_name: Option<String>,
+    // Location of the `DW_TAG_subprogram` entry of this function.
+    pub(crate) unit: usize,
+    pub(crate) offset: gimli::UnitOffset,
+}
+
+impl Function {
+    /// Returns the name of the function as written in the source code, e.g. `main`.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the symbol name of the function, e.g. `_ZN5hello4main17h1c6a1d6e8a8b9d6bE`.
+    /// Functions which are not mangled (e.g. `#[no_mangle]` or C functions) usually don't
+    /// have a linkage name.
+    pub fn linkage_name(&self) -> Option<&str> {
+        self.linkage_name.as_deref()
+    }
+
+    /// Returns the demangled linkage name, without the hash suffix of Rust names, e.g.
+    /// `hello::main`.
+    pub fn demangled_name(&self) -> Option<&str> {
+        self.demangled_name.as_deref()
+    }
+
+    /// Returns the address ranges of the code of the function.
+    /// Optimized functions can consist of several ranges, e.g. if cold code is moved elsewhere.
+    pub fn ranges(&self) -> &[Range<usize>] {
+        &self.ranges
+    }
+
+    /// Returns the path of the source file containing the function.
+    pub fn decl_file(&self) -> Option<&str> {
+        self.decl_file.as_deref()
+    }
+
+    /// Returns the source line on which the function is declared.
+    pub fn decl_line(&self) -> Option<u64> {
+        self.decl_line
+    }
+
+    /// Returns the name of the compilation unit which contains the function.
+    pub fn unit_name(&self) -> Option<&str> {
+        self.unit_name.as_deref()
+    }
+}
+
+/// All functions which have code in the debuggee.
+pub(crate) struct FunctionIndex {
+    functions: Vec<Function>,
+    // Address ranges of all functions along with the index of the function, sorted by address.
+    ranges: Vec<(Range<usize>, usize)>,
+}
+
+impl FunctionIndex {
+    pub(super) fn new(
+        dwarf: &gimli::Dwarf<Reader>,
+        units: &[gimli::Unit<Reader>],
+    ) -> Result<FunctionIndex, Box<dyn std::error::Error>> {
+        let mut functions = Vec::new();
+        let mut ranges = Vec::new();
+
+        for (unit_index, unit) in units.iter().enumerate() {
+            let unit_name = match &unit.name {
+                Some(name) => Some(name.to_string_lossy()?.into_owned()),
+                None => None,
+            };
+
+            let mut entries = unit.entries();
+            while let Some((_, entry)) = entries.next_dfs()? {
+                if entry.tag() != gimli::DW_TAG_subprogram {
+                    continue;
+                }
+
+                // Declarations and abstract instances of inlined functions don't have any code.
+                let function_ranges = entry_ranges(dwarf, unit, entry)?;
+                if function_ranges.is_empty() {
+                    continue;
+                }
+
+                let offset = entry.offset();
+                let FunctionNames {
+                    name,
+                    linkage_name,
+                    demangled_name,
+                } = function_names(dwarf, units, unit_index, offset)?;
+
+                let decl_file = match find_attr(units, unit_index, offset, gimli::DW_AT_decl_file)?
+                {
+                    Some((unit_index, value)) => {
+                        line::file_attr_path(dwarf, &units[unit_index], value)?
+                    }
+                    None => None,
+                };
+                let decl_line = find_attr(units, unit_index, offset, gimli::DW_AT_decl_line)?
+                    .and_then(|(_, value)| value.udata_value());
+
+                for range in &function_ranges {
+                    ranges.push((range.clone(), functions.len()));
+                }
+                functions.push(Function {
+                    name,
+                    linkage_name,
+                    demangled_name,
+                    ranges: function_ranges,
+                    decl_file,
+                    decl_line,
+                    unit_name: unit_name.clone(),
+                    unit: unit_index,
+                    offset,
+                });
+            }
+        }
+
+        ranges.sort_by_key(|(range, _)| range.start);
+
+        Ok(FunctionIndex { functions, ranges })
+    }
+
+    /// Finds the function whose code contains `addr`.
+    pub(crate) fn find_by_addr(&self, addr: usize) -> Option<&Function> {
+        let index = self
+            .ranges
+            .partition_point(|(range, _)| range.start <= addr);
+        let (range, function) = self.ranges.get(index.checked_sub(1)?)?;
+        if !range.contains(&addr) {
+            return None;
+        }
+        Some(&self.functions[*function])
+    }
+}
+
+/// Returns the address ranges of the code described by an entry.
+pub(super) fn entry_ranges(
+    dwarf: &gimli::Dwarf<Reader>,
+    unit: &gimli::Unit<Reader>,
+    entry: &gimli::DebuggingInformationEntry<Reader>,
+) -> Result<Vec<Range<usize>>, Box<dyn std::error::Error>> {
+    let mut ranges = Vec::new();
+    let mut die_ranges = dwarf.die_ranges(unit, entry)?;
+    while let Some(range) = die_ranges.next()? {
+        // Discarded functions are relocated to address 0 by the linker.
+        if range.begin != 0 && range.begin < range.end {
+            ranges.push(range.begin as usize..range.end as usize);
+        }
+    }
+    Ok(ranges)
+}
+
+/// The names of a function, see the accessors of [`Function`].
+pub(super) struct FunctionNames {
+    pub(super) name: Option<String>,
+    pub(super) linkage_name: Option<String>,
+    pub(super) demangled_name: Option<String>,
+}
+
+/// Returns the names of the function described by an entry, e.g. a `DW_TAG_subprogram` or a
+/// `DW_TAG_inlined_subroutine`.
+pub(super) fn function_names(
+    dwarf: &gimli::Dwarf<Reader>,
+    units: &[gimli::Unit<Reader>],
+    unit_index: usize,
+    offset: gimli::UnitOffset,
+) -> Result<FunctionNames, Box<dyn std::error::Error>> {
+    let string_attr = |name| -> Result<_, Box<dyn std::error::Error>> {
+        match find_attr(units, unit_index, offset, name)? {
+            Some((unit_index, value)) => Ok(Some(
+                dwarf
+                    .attr_string(&units[unit_index], value)?
+                    .to_string_lossy()?
+                    .into_owned(),
+            )),
+            None => Ok(None),
+        }
+    };
+    let name = string_attr(gimli::DW_AT_name)?;
+    let linkage_name = match string_attr(gimli::DW_AT_linkage_name)? {
+        Some(linkage_name) => Some(linkage_name),
+        // Used by older compilers.
+        None => string_attr(gimli::DW_AT_MIPS_linkage_name)?,
+    };
+    let demangled_name = linkage_name.as_deref().and_then(demangle);
+    Ok(FunctionNames {
+        name,
+        linkage_name,
+        demangled_name,
+    })
+}
+
+/// Finds an attribute of an entry, following `DW_AT_abstract_origin` and `DW_AT_specification`
+/// references to other entries describing the same function if the entry doesn't have it.
+/// Returns the index of the unit of the entry which has the attribute along with the value.
+pub(super) fn find_attr<'a>(
+    units: &[gimli::Unit<Reader<'a>>],
+    mut unit_index: usize,
+    mut offset: gimli::UnitOffset,
+    name: gimli::DwAt,
+) -> Result<Option<(usize, AttributeValue<'a>)>, Box<dyn std::error::Error>> {
+    // Malformed debug info could contain a cycle of references.
+    for _ in 0..8 {
+        let entry = units[unit_index].entry(offset)?;
+        if let Some(value) = entry.attr_value(name)? {
+            return Ok(Some((unit_index, value)));
+        }
+        let reference = match entry.attr_value(gimli::DW_AT_abstract_origin)? {
+            Some(reference) => reference,
+            None => match entry.attr_value(gimli::DW_AT_specification)? {
+                Some(reference) => reference,
+                None => return Ok(None),
+            },
+        };
+        match resolve_ref(units, unit_index, reference) {
+            Some(target) => (unit_index, offset) = target,
+            None => return Ok(None),
+        }
+    }
+    Ok(None)
+}
+
+/// Resolves a reference to another entry to the index of its unit and its offset in the unit.
+pub(super) fn resolve_ref(
+    units: &[gimli::Unit<Reader>],
+    unit_index: usize,
+    reference: AttributeValue,
+) -> Option<(usize, gimli::UnitOffset)> {
+    match reference {
+        gimli::AttributeValue::UnitRef(offset) => Some((unit_index, offset)),
+        gimli::AttributeValue::DebugInfoRef(offset) => {
+            units.iter().enumerate().find_map(|(index, unit)| {
+                Some((
+                    index,
+                    gimli::UnitSectionOffset::DebugInfoOffset(offset).to_unit_offset(unit)?,
+                ))
+            })
+        }
+        _ => None,
+    }
+}
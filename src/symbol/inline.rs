@@ -0,0 +1,143 @@
+// Functions inlined into other functions, read from the `DW_TAG_inlined_subroutine` entries in
+// `.debug_info`.
+
+use super::{
+    function::{entry_ranges, function_names, FunctionNames},
+    line, Reader, SourceLocation,
+};
+
+/// A function in the chain of inlined functions at an address, see
+/// [`Dwarf::get_inline_frames`](super::Dwarf::get_inline_frames).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InlineFrame {
+    /// Name of the function as written in the source code, e.g. `main`.
+    pub name: Option<String>,
+    /// Symbol name of the function, if it is mangled.
+    pub linkage_name: Option<String>,
+    /// Demangled linkage name without the hash, e.g. `hello::main`.
+    pub demangled_name: Option<String>,
+    /// The current position within the function. For all frames but the innermost one, this is
+    /// the place where the next inner function has been inlined.
+    pub location: Option<SourceLocation>,
+    /// `true` if the function has been inlined into the next outer frame.
+    pub inlined: bool,
+}
+
+/// Finds the `DW_TAG_inlined_subroutine` entries containing `addr` among the children of the
+/// function entry at `offset`, from the outermost to the innermost one.
+fn find_inlined_entries(
+    dwarf: &gimli::Dwarf<Reader>,
+    unit: &gimli::Unit<Reader>,
+    offset: gimli::UnitOffset,
+    addr: usize,
+) -> Result<Vec<gimli::UnitOffset>, Box<dyn std::error::Error>> {
+    fn find_in_children(
+        dwarf: &gimli::Dwarf<Reader>,
+        unit: &gimli::Unit<Reader>,
+        node: gimli::EntriesTreeNode<Reader>,
+        addr: usize,
+        inlined: &mut Vec<gimli::UnitOffset>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut children = node.children();
+        while let Some(child) = children.next()? {
+            let entry = child.entry();
+            match entry.tag() {
+                // Inlined functions can be nested in lexical blocks.
+                gimli::DW_TAG_inlined_subroutine | gimli::DW_TAG_lexical_block => {}
+                _ => continue,
+            }
+            if !entry_ranges(dwarf, unit, entry)?
+                .iter()
+                .any(|range| range.contains(&addr))
+            {
+                continue;
+            }
+            if entry.tag() == gimli::DW_TAG_inlined_subroutine {
+                inlined.push(entry.offset());
+            }
+            return find_in_children(dwarf, unit, child, addr, inlined);
+        }
+        Ok(())
+    }
+
+    let mut inlined = Vec::new();
+    let mut tree = unit.entries_tree(Some(offset))?;
+    find_in_children(dwarf, unit, tree.root()?, addr, &mut inlined)?;
+    Ok(inlined)
+}
+
+/// Builds the inline chain of `addr` within the function entry at `offset`, innermost first.
+pub(super) fn inline_frames(
+    dwarf: &gimli::Dwarf<Reader>,
+    units: &[gimli::Unit<Reader>],
+    lines: &line::LineTable,
+    unit_index: usize,
+    offset: gimli::UnitOffset,
+    addr: usize,
+) -> Result<Vec<InlineFrame>, Box<dyn std::error::Error>> {
+    let unit = &units[unit_index];
+    let inlined = find_inlined_entries(dwarf, unit, offset, addr)?;
+
+    let mut location = lines.find_row(addr).and_then(|row| lines.location(row));
+    let mut frames = Vec::new();
+    // Go from the innermost function outwards, using the call site of each inlined function as
+    // the location in its caller.
+    for &entry_offset in inlined.iter().rev() {
+        let FunctionNames {
+            name,
+            linkage_name,
+            demangled_name,
+        } = function_names(dwarf, units, unit_index, entry_offset)?;
+        frames.push(InlineFrame {
+            name,
+            linkage_name,
+            demangled_name,
+            location: location.take(),
+            inlined: true,
+        });
+        location = call_location(dwarf, units, unit_index, entry_offset)?;
+    }
+
+    let FunctionNames {
+        name,
+        linkage_name,
+        demangled_name,
+    } = function_names(dwarf, units, unit_index, offset)?;
+    frames.push(InlineFrame {
+        name,
+        linkage_name,
+        demangled_name,
+        location,
+        inlined: false,
+    });
+    Ok(frames)
+}
+
+/// Returns the location an inlined function has been called from.
+fn call_location(
+    dwarf: &gimli::Dwarf<Reader>,
+    units: &[gimli::Unit<Reader>],
+    unit_index: usize,
+    offset: gimli::UnitOffset,
+) -> Result<Option<SourceLocation>, Box<dyn std::error::Error>> {
+    let unit = &units[unit_index];
+    let entry = unit.entry(offset)?;
+    let file = match entry.attr_value(gimli::DW_AT_call_file)? {
+        Some(value) => line::file_attr_path(dwarf, unit, value)?,
+        None => None,
+    };
+    let line = entry
+        .attr_value(gimli::DW_AT_call_line)?
+        .and_then(|value| value.udata_value());
+    let column = entry
+        .attr_value(gimli::DW_AT_call_column)?
+        .and_then(|value| value.udata_value());
+    match (file, line) {
+        (Some(file), Some(line)) if line != 0 => Ok(Some(SourceLocation {
+            file,
+            line,
+            column: column.filter(|&column| column != 0),
+        })),
+        _ => Ok(None),
+    }
+}
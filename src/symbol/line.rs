@@ -0,0 +1,207 @@
+// Source line information, read from the line number programs in `.debug_line`.
+
+use gimli::Reader as _;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use super::{AttributeValue, Reader};
+
+/// A position in the source code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+    /// Path of the source file, as recorded by the compiler.
+    pub file: String,
+    pub line: u64,
+    /// `None` if the location refers to the whole line.
+    pub column: Option<u64>,
+}
+
+/// A row of the line number table, describing the code starting at `address` up to the address
+/// of the next row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct LineRow {
+    pub(crate) address: usize,
+    // Index into `LineTable::files`.
+    pub(crate) file: usize,
+    // `0` if the code doesn't correspond to any source line, e.g. compiler-generated code.
+    pub(crate) line: u64,
+    // `0` if the code corresponds to the whole line.
+    pub(crate) column: u64,
+    // Whether this is a recommended place for a breakpoint, e.g. the start of a statement.
+    pub(crate) is_stmt: bool,
+    // Marks the first address after a sequence of rows, which is not covered by the table.
+    end_sequence: bool,
+}
+
+/// The line number tables of all compilation units, merged into a single table.
+pub(crate) struct LineTable {
+    // Sorted by address.
+    rows: Vec<LineRow>,
+    // Paths of the source files, deduplicated across compilation units.
+    files: Vec<String>,
+}
+
+impl LineTable {
+    pub(super) fn new(
+        dwarf: &gimli::Dwarf<Reader>,
+    ) -> Result<LineTable, Box<dyn std::error::Error>> {
+        let mut rows = Vec::new();
+        let mut files = Vec::new();
+        let mut file_indices = HashMap::new();
+
+        let mut units = dwarf.units();
+        while let Some(header) = units.next()? {
+            let unit = dwarf.unit(header)?;
+            let program = match &unit.line_program {
+                Some(program) => program.clone(),
+                None => continue,
+            };
+
+            // Maps file indices of this unit to indices into `files`.
+            let mut unit_files = HashMap::new();
+            let mut sequence = Vec::new();
+            let mut program_rows = program.rows();
+            while let Some((header, row)) = program_rows.next_row()? {
+                let file = match unit_files.get(&row.file_index()) {
+                    Some(&file) => file,
+                    None => {
+                        let path = match row.file(header) {
+                            Some(file) => file_path(dwarf, &unit, header, file)?,
+                            None => String::new(),
+                        };
+                        let file = *file_indices.entry(path).or_insert_with_key(|path| {
+                            files.push(path.clone());
+                            files.len() - 1
+                        });
+                        unit_files.insert(row.file_index(), file);
+                        file
+                    }
+                };
+
+                sequence.push(LineRow {
+                    address: row.address() as usize,
+                    file,
+                    line: row.line().unwrap_or(0),
+                    column: match row.column() {
+                        gimli::ColumnType::LeftEdge => 0,
+                        gimli::ColumnType::Column(column) => column,
+                    },
+                    is_stmt: row.is_stmt(),
+                    end_sequence: row.end_sequence(),
+                });
+
+                if row.end_sequence() {
+                    // The linker relocates sequences of discarded functions to address 0, so they
+                    // would overlap with each other.
+                    if sequence[0].address != 0 {
+                        rows.append(&mut sequence);
+                    }
+                    sequence.clear();
+                }
+            }
+        }
+
+        // The end of a sequence has to come before the start of the next one at the same address.
+        rows.sort_by_key(|row| (row.address, !row.end_sequence));
+
+        Ok(LineTable { rows, files })
+    }
+
+    /// Finds the row which covers `addr`.
+    /// If there are several rows for the same address, the last one is used.
+    pub(crate) fn find_row(&self, addr: usize) -> Option<&LineRow> {
+        let index = self.rows.partition_point(|row| row.address <= addr);
+        let row = self.rows.get(index.checked_sub(1)?)?;
+        if row.end_sequence {
+            return None;
+        }
+        Some(row)
+    }
+
+    /// Returns the source location of a row, if it corresponds to a source line.
+    pub(crate) fn location(&self, row: &LineRow) -> Option<SourceLocation> {
+        if row.line == 0 {
+            return None;
+        }
+        Some(SourceLocation {
+            file: self.files[row.file].clone(),
+            line: row.line,
+            column: if row.column == 0 {
+                None
+            } else {
+                Some(row.column)
+            },
+        })
+    }
+
+    /// Returns the addresses of the statements at which the code of a source line starts.
+    /// `file` can also be a suffix of the path, e.g. the file name only.
+    pub(crate) fn find_line_addresses(&self, file: &Path, line: u64) -> Vec<usize> {
+        let files = self
+            .files
+            .iter()
+            .map(|path| Path::new(path).ends_with(file))
+            .collect::<Vec<_>>();
+
+        let mut addrs = Vec::new();
+        let mut prev_row: Option<&LineRow> = None;
+        for row in &self.rows {
+            let matches = !row.end_sequence && files[row.file] && row.line == line;
+            // Consecutive rows of the same line are parts of the same code block.
+            let continued = prev_row.is_some_and(|prev| {
+                !prev.end_sequence && prev.file == row.file && prev.line == row.line
+            });
+            if matches && row.is_stmt && !continued {
+                addrs.push(row.address);
+            }
+            prev_row = Some(row);
+        }
+        addrs.dedup();
+        addrs
+    }
+}
+
+/// Builds the path of a source file from the line program header, which may be relative to the
+/// directory of the file, which in turn may be relative to the compilation directory.
+fn file_path(
+    dwarf: &gimli::Dwarf<Reader>,
+    unit: &gimli::Unit<Reader>,
+    header: &gimli::LineProgramHeader<Reader>,
+    file: &gimli::FileEntry<Reader>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let mut path = PathBuf::new();
+    if let Some(comp_dir) = &unit.comp_dir {
+        path.push(&*comp_dir.to_string_lossy()?);
+    }
+    if let Some(directory) = file.directory(header) {
+        path.push(&*dwarf.attr_string(unit, directory)?.to_string_lossy()?);
+    }
+    path.push(
+        &*dwarf
+            .attr_string(unit, file.path_name())?
+            .to_string_lossy()?,
+    );
+    Ok(path.to_string_lossy().into_owned())
+}
+
+/// Returns the path of a source file referenced by an attribute, e.g. `DW_AT_decl_file`.
+pub(super) fn file_attr_path(
+    dwarf: &gimli::Dwarf<Reader>,
+    unit: &gimli::Unit<Reader>,
+    value: AttributeValue,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let index = match value {
+        gimli::AttributeValue::FileIndex(index) | gimli::AttributeValue::Udata(index) => index,
+        _ => return Ok(None),
+    };
+    let header = match &unit.line_program {
+        Some(program) => program.header(),
+        None => return Ok(None),
+    };
+    match header.file(index) {
+        Some(file) => Ok(Some(file_path(dwarf, unit, header, file)?)),
+        None => Ok(None),
+    }
+}
@@ -1,23 +1,49 @@
 // This module provides a naive implementation of symbolication for the time being.
 // It should be expanded to support multiple data sources.
 
+mod demangle;
+mod function;
+mod inline;
+mod line;
+mod types;
+mod unwind;
+mod variable;
+
 use gimli::read::{EvaluationResult, Reader as _};
 use object::{
-    read::{Object, ObjectSection, Symbol},
+    read::{Object, ObjectSection, ObjectSegment, Symbol},
     SymbolKind,
 };
 use std::{
     borrow::Cow,
     collections::{BTreeMap, HashMap},
     fs::File,
+    path::{Path, PathBuf},
     rc::Rc,
 };
 
+pub use demangle::demangle;
+pub use function::Function;
+use function::FunctionIndex;
+pub use inline::InlineFrame;
+pub(crate) use line::LineRow;
+use line::LineTable;
+pub use line::SourceLocation;
+pub use types::{
+    BaseEncoding, EnumType, Enumerator, Field, StructType, TemplateParam, Type, TypeId, TypeKind,
+    Variant, VariantPart,
+};
+use unwind::CallFrameInfo;
+pub use unwind::{UnwindRegisters, UNWIND_REGISTERS};
+pub(crate) use unwind::{RETURN_ADDRESS, RSP};
+use variable::UnitExpression;
+pub use variable::{Variable, VariableKind, VariableLocation};
+
 macro_rules! dwarf_attr_or_continue {
     (str($dwarf:ident,$unit:ident) $entry:ident.$name:ident) => {
         $dwarf
             .attr_string(&$unit, dwarf_attr_or_continue!($entry.$name).value())?
-            .to_string()?;
+            .to_string()?
     };
     ($entry:ident.$name:ident) => {
         if let Some(attr) = $entry.attr(gimli::$name)? {
@@ -48,8 +74,8 @@ impl<T: ?Sized> std::ops::Deref for RcCow<'_, T> {
 
     fn deref(&self) -> &Self::Target {
         match self {
-            RcCow::Owned(rc) => &**rc,
-            RcCow::Borrowed(slice) => &**slice,
+            RcCow::Owned(rc) => rc,
+            RcCow::Borrowed(slice) => slice,
         }
     }
 }
@@ -58,13 +84,28 @@ unsafe impl<T: ?Sized> gimli::StableDeref for RcCow<'_, T> {}
 unsafe impl<T: ?Sized> gimli::CloneStableDeref for RcCow<'_, T> {}
 
 type Reader<'a> = gimli::EndianReader<gimli::RunTimeEndian, RcCow<'a, [u8]>>;
+type AttributeValue<'a> = gimli::AttributeValue<Reader<'a>>;
 
 pub struct ParsedDwarf<'a> {
+    #[allow(dead_code)]
     object: object::File<'a>,
     dwarf: gimli::Dwarf<Reader<'a>>,
     vars: BTreeMap<String, usize>,
+    var_types: HashMap<String, TypeId>,
+    // Concrete types of trait objects, by the addresses of their vtables.
+    vtables: HashMap<usize, TypeId>,
     symbols: Vec<Symbol<'a>>,
     symbol_names: HashMap<&'a str, usize>,
+    // Demangled symbol names, both with and without the hash suffix.
+    demangled_names: HashMap<String, usize>,
+    lines: LineTable,
+    units: Vec<gimli::Unit<Reader<'a>>>,
+    functions: FunctionIndex,
+    call_frames: CallFrameInfo<'a>,
+    // Size and alignment of the thread-local storage block of the executable.
+    tls_block: Option<(usize, usize)>,
+    // The lowest address of the loadable segments, at which the start of the file is mapped.
+    load_address: u64,
 }
 
 impl<'a> ParsedDwarf<'a> {
@@ -103,11 +144,16 @@ impl<'a> ParsedDwarf<'a> {
         // Create `EndianSlice`s for all of the sections.
         let dwarf = gimli::Dwarf::load(loader, sup_loader)?;
 
-        let mut units = dwarf.units();
+        let mut units = Vec::new();
+        let mut headers = dwarf.units();
+        while let Some(header) = headers.next()? {
+            units.push(dwarf.unit(header)?);
+        }
 
         let mut vars = BTreeMap::new();
-        while let Some(header) = units.next()? {
-            let unit = dwarf.unit(header)?;
+        let mut var_types = HashMap::new();
+        let mut vtables = HashMap::new();
+        for (unit_index, unit) in units.iter().enumerate() {
             let mut entries = unit.entries();
             while let Some((_, entry)) = entries.next_dfs()? {
                 if entry.tag() == gimli::DW_TAG_variable {
@@ -120,6 +166,21 @@ impl<'a> ParsedDwarf<'a> {
                         let mut eval = expr.evaluation(unit.encoding());
                         match eval.evaluate()? {
                             EvaluationResult::RequiresRelocatedAddress(reloc_addr) => {
+                                let type_id = entry
+                                    .attr_value(gimli::DW_AT_type)?
+                                    .and_then(|value| types::type_id(&units, unit_index, value));
+                                if let Some(type_id) = type_id {
+                                    var_types.insert(name.to_owned(), type_id);
+                                    // Rust vtables are named `<T as Trait>::{vtable}`, their
+                                    // types refer to `T`.
+                                    if name.ends_with("::{vtable}") {
+                                        if let Some(concrete) =
+                                            types::containing_type(&units, type_id)?
+                                        {
+                                            vtables.insert(reloc_addr as usize, concrete);
+                                        }
+                                    }
+                                }
                                 vars.insert(name.to_owned(), reloc_addr as usize);
                             }
                             _ev_res => {} // do nothing for now
@@ -129,6 +190,54 @@ impl<'a> ParsedDwarf<'a> {
             }
         }
 
+        let lines = LineTable::new(&dwarf)?;
+        let functions = FunctionIndex::new(&dwarf, &units)?;
+
+        let section_address = |name| {
+            object
+                .section_by_name(name)
+                .map(|section| section.address())
+        };
+        let mut eh_frame_bases = gimli::BaseAddresses::default();
+        if let Some(address) = section_address(".eh_frame") {
+            eh_frame_bases = eh_frame_bases.set_eh_frame(address);
+        }
+        if let Some(address) = section_address(".text") {
+            eh_frame_bases = eh_frame_bases.set_text(address);
+        }
+        if let Some(address) = section_address(".got") {
+            eh_frame_bases = eh_frame_bases.set_got(address);
+        }
+        let call_frames = CallFrameInfo::new(
+            gimli::EhFrame::from(loader(gimli::SectionId::EhFrame)?),
+            eh_frame_bases,
+            gimli::DebugFrame::from(loader(gimli::SectionId::DebugFrame)?),
+            if object.is_64() { 8 } else { 4 },
+        )?;
+
+        // The initialized and zero-initialized thread-local variables are adjacent.
+        let tls_sections: Vec<_> = [".tdata", ".tbss"]
+            .iter()
+            .filter_map(|name| object.section_by_name(name))
+            .collect();
+        let tls_block = match (tls_sections.first(), tls_sections.last()) {
+            (Some(first), Some(last)) => Some((
+                (last.address() + last.size() - first.address()) as usize,
+                tls_sections
+                    .iter()
+                    .map(|section| section.align() as usize)
+                    .max()
+                    .unwrap_or(1),
+            )),
+            _ => None,
+        };
+
+        let load_address = object
+            .segments()
+            .map(|segment| segment.address())
+            .min()
+            .unwrap_or(0);
+
         let mut symbols: Vec<_> = object
             .symbols()
             .chain(object.dynamic_symbols())
@@ -153,9 +262,19 @@ impl<'a> ParsedDwarf<'a> {
         symbols.sort_by_key(|sym| sym.address());
 
         let mut symbol_names = HashMap::new();
+        let mut demangled_names = HashMap::new();
         for sym in &symbols {
             if let Some(name) = sym.name() {
                 symbol_names.insert(name, sym.address() as usize);
+
+                // Several symbols can have the same path without the hash, e.g. instances of a
+                // generic function. The one with the lowest address is used.
+                let demangled = demangle::demangle(name).into_iter();
+                for demangled in demangled.chain(demangle::demangle_with_hash(name)) {
+                    demangled_names
+                        .entry(demangled)
+                        .or_insert(sym.address() as usize);
+                }
             }
         }
 
@@ -163,16 +282,38 @@ impl<'a> ParsedDwarf<'a> {
             object,
             dwarf,
             vars,
+            var_types,
+            vtables,
             symbols,
             symbol_names,
+            demangled_names,
+            lines,
+            units,
+            functions,
+            call_frames,
+            tls_block,
+            load_address,
         })
     }
 
     pub fn get_symbol_address(&self, name: &str) -> Option<usize> {
-        self.symbol_names.get(name).copied()
+        self.symbol_names
+            .get(name)
+            .or_else(|| self.demangled_names.get(name))
+            .copied()
     }
 
     pub fn get_address_symbol(&self, addr: usize) -> Option<String> {
+        if let Some(function) = self.functions.find_by_addr(addr) {
+            if let Some(name) = function.demangled_name() {
+                return Some(name.to_string());
+            }
+            if let Some(name) = function.linkage_name().or_else(|| function.name()) {
+                return Some(name.to_string());
+            }
+        }
+
+        // Fall back to the symbol table for code without debug info.
         let index = match self
             .symbols
             .binary_search_by(|sym| sym.address().cmp(&(addr as u64)))
@@ -185,29 +326,163 @@ impl<'a> ParsedDwarf<'a> {
             Err(index) => index - 1,
         };
         let symbol = &self.symbols[index];
-        if self.symbols.get(index + 1).map(|sym| sym.address()) <= Some(addr as u64) {
+        if addr as u64 >= symbol.address() + symbol.size() {
             return None;
         }
-        Some(symbol.name()?.to_string())
-
-        // FIXME `size` is wrong in some cases. Once this is solved use the following instead.
-        /*
-        let sym = symbols.binary_search_by(|sym| {
-            use std::cmp::Ordering;
-            if svma < Svma(sym.address()) {
-                Ordering::Greater
-            } else if svma < Svma(sym.address() + sym.size()) {
-                Ordering::Equal
-            } else {
-                Ordering::Less
-            }
-        }).ok().and_then(|idx| symbols.get(idx));
-        */
+        let name = symbol.name()?;
+        Some(demangle::demangle(name).unwrap_or_else(|| name.to_string()))
+    }
+
+    pub fn get_address_function(&self, addr: usize) -> Option<&Function> {
+        self.functions.find_by_addr(addr)
+    }
+
+    pub fn get_inline_frames(
+        &self,
+        addr: usize,
+    ) -> Result<Vec<InlineFrame>, Box<dyn std::error::Error>> {
+        let function = match self.functions.find_by_addr(addr) {
+            Some(function) => function,
+            None => return Ok(Vec::new()),
+        };
+        inline::inline_frames(
+            &self.dwarf,
+            &self.units,
+            &self.lines,
+            function.unit,
+            function.offset,
+            addr,
+        )
+    }
+
+    pub fn get_address_variables(
+        &self,
+        addr: usize,
+    ) -> Result<Vec<Variable>, Box<dyn std::error::Error>> {
+        let function = match self.functions.find_by_addr(addr) {
+            Some(function) => function,
+            None => return Ok(Vec::new()),
+        };
+        variable::scope_variables(
+            &self.dwarf,
+            &self.units,
+            function.unit,
+            function.offset,
+            addr,
+        )
     }
 
     pub fn get_var_address(&self, name: &str) -> Option<usize> {
         self.vars.get(name).cloned()
     }
+
+    pub fn get_var_type(&self, name: &str) -> Option<TypeId> {
+        self.var_types.get(name).copied()
+    }
+
+    pub fn get_vtable_type(&self, vtable: usize) -> Option<TypeId> {
+        self.vtables.get(&vtable).copied()
+    }
+
+    pub fn get_type(&self, id: TypeId) -> Result<Type, Box<dyn std::error::Error>> {
+        types::parse_type(&self.dwarf, &self.units, id)
+    }
+
+    pub fn get_qualified_type_name(
+        &self,
+        id: TypeId,
+    ) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        types::qualified_type_name(&self.dwarf, &self.units, id)
+    }
+
+    pub fn find_type(
+        &self,
+        qualified_name: &str,
+    ) -> Result<Option<TypeId>, Box<dyn std::error::Error>> {
+        types::find_type(&self.dwarf, &self.units, qualified_name)
+    }
+
+    pub(crate) fn get_frame_base(
+        &self,
+        addr: usize,
+    ) -> Result<Option<(VariableLocation, usize)>, Box<dyn std::error::Error>> {
+        let function = match self.functions.find_by_addr(addr) {
+            Some(function) => function,
+            None => return Ok(None),
+        };
+        let unit = &self.units[function.unit];
+        match unit
+            .entry(function.offset)?
+            .attr_value(gimli::DW_AT_frame_base)?
+        {
+            Some(value) => Ok(variable::attr_location(&self.dwarf, unit, value)?
+                .map(|location| (location, function.unit))),
+            None => Ok(None),
+        }
+    }
+
+    pub(crate) fn get_call_site_value(
+        &self,
+        return_addr: usize,
+        register: u16,
+    ) -> Result<Option<UnitExpression>, Box<dyn std::error::Error>> {
+        let function = match self.functions.find_by_addr(return_addr.wrapping_sub(1)) {
+            Some(function) => function,
+            None => return Ok(None),
+        };
+        Ok(variable::call_site_value(
+            &self.units,
+            function.unit,
+            function.offset,
+            return_addr,
+            register,
+        )?
+        .map(|expr| UnitExpression {
+            expr,
+            unit: function.unit,
+        }))
+    }
+
+    pub(crate) fn get_indexed_address(
+        &self,
+        unit: usize,
+        index: gimli::DebugAddrIndex<usize>,
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        Ok(self.dwarf.address(&self.units[unit], index)? as usize)
+    }
+
+    pub(crate) fn get_unit_encoding(&self, unit: usize) -> gimli::Encoding {
+        self.units[unit].encoding()
+    }
+
+    pub(crate) fn get_tls_block(&self) -> Option<(usize, usize)> {
+        self.tls_block
+    }
+
+    pub(crate) fn get_load_address(&self) -> u64 {
+        self.load_address
+    }
+
+    pub fn get_address_location(&self, addr: usize) -> Option<SourceLocation> {
+        self.lines.location(self.lines.find_row(addr)?)
+    }
+
+    pub fn get_line_addresses(&self, file: &str, line: u64) -> Vec<usize> {
+        self.lines.find_line_addresses(Path::new(file), line)
+    }
+
+    pub(crate) fn get_line_row(&self, addr: usize) -> Option<LineRow> {
+        self.lines.find_row(addr).copied()
+    }
+
+    pub(crate) fn unwind_frame(
+        &self,
+        addr: usize,
+        regs: &UnwindRegisters,
+        read_memory: &mut dyn FnMut(usize) -> Result<u64, Box<dyn std::error::Error>>,
+    ) -> Result<Option<(usize, UnwindRegisters)>, Box<dyn std::error::Error>> {
+        self.call_frames.unwind(addr, regs, read_memory)
+    }
 }
 
 mod inner {
@@ -217,6 +492,7 @@ mod inner {
     pub struct Dwarf {
         _mmap: memmap::Mmap,
         parsed: ManuallyDrop<ParsedDwarf<'static>>,
+        path: PathBuf,
     }
 
     impl Dwarf {
@@ -226,12 +502,14 @@ mod inner {
         pub fn new(path: &str) -> Result<Dwarf, Box<dyn std::error::Error>> {
             // Load ELF/Mach-O object file
             let file = File::open(path)?;
+            // Memory maps list the canonical paths of the mapped files.
+            let path = std::fs::canonicalize(path)?;
 
             // Safety: Not really, this assumes that the backing file will not be truncated or
             // written to while it is used by us.
             let mmap = unsafe { memmap::Mmap::map(&file)? };
 
-            let parsed = ManuallyDrop::new(ParsedDwarf::new(&*mmap)?);
+            let parsed = ManuallyDrop::new(ParsedDwarf::new(&mmap)?);
 
             // Safety: `parsed` doesn't outlive `mmap`, from which it borrows, because no reference
             // to `parsed` can be obtained without the lifetime being shortened to be smaller than
@@ -245,11 +523,17 @@ mod inner {
             Ok(Dwarf {
                 _mmap: mmap,
                 parsed,
+                path,
             })
         }
 
+        /// Returns the canonical path of the file the debug info has been loaded from.
+        pub fn path(&self) -> &Path {
+            &self.path
+        }
+
         pub fn rent<T>(&self, f: impl for<'a> FnOnce(&ParsedDwarf<'a>) -> T) -> T {
-            f(&*self.parsed)
+            f(&self.parsed)
         }
     }
 
@@ -265,15 +549,150 @@ mod inner {
 pub use inner::Dwarf;
 
 impl Dwarf {
+    /// Returns the address of a symbol. Mangled Rust and C++ symbols can also be found by their
+    /// demangled path, with or without the hash suffix, e.g. `hello::main`.
     pub fn get_symbol_address(&self, name: &str) -> Option<usize> {
         self.rent(|parsed| parsed.get_symbol_address(name))
     }
 
+    /// Returns the name of the function containing `addr`, demangled without the hash suffix.
+    /// The symbol name is available from [`Function::linkage_name`].
     pub fn get_address_symbol(&self, addr: usize) -> Option<String> {
         self.rent(|parsed| parsed.get_address_symbol(addr))
     }
 
+    /// Returns the function whose code contains `addr`, as described by the debug info.
+    pub fn get_address_function(&self, addr: usize) -> Option<Function> {
+        self.rent(|parsed| parsed.get_address_function(addr).cloned())
+    }
+
+    /// Returns the chain of functions inlined at `addr`, innermost first.
+    /// The last frame is the function which actually contains the code. The result is empty if
+    /// `addr` isn't part of any known function.
+    pub fn get_inline_frames(
+        &self,
+        addr: usize,
+    ) -> Result<Vec<InlineFrame>, Box<dyn std::error::Error>> {
+        self.rent(|parsed| parsed.get_inline_frames(addr))
+    }
+
+    /// Returns the parameters and local variables in scope at `addr`, innermost scope first, so
+    /// the first variable with a name shadows any later ones. This includes the variables of
+    /// the functions inlined at `addr`.
+    pub fn get_address_variables(
+        &self,
+        addr: usize,
+    ) -> Result<Vec<Variable>, Box<dyn std::error::Error>> {
+        self.rent(|parsed| parsed.get_address_variables(addr))
+    }
+
     pub fn get_var_address(&self, name: &str) -> Option<usize> {
         self.rent(|parsed| parsed.get_var_address(name))
     }
+
+    /// Returns the type of a global variable.
+    pub fn get_var_type(&self, name: &str) -> Option<TypeId> {
+        self.rent(|parsed| parsed.get_var_type(name))
+    }
+
+    /// Returns the concrete type of the trait objects which use the vtable at `vtable`.
+    pub fn get_vtable_type(&self, vtable: usize) -> Option<TypeId> {
+        self.rent(|parsed| parsed.get_vtable_type(vtable))
+    }
+
+    /// Returns the description of a type, e.g. the type of a variable.
+    pub fn get_type(&self, id: TypeId) -> Result<Type, Box<dyn std::error::Error>> {
+        self.rent(|parsed| parsed.get_type(id))
+    }
+
+    /// Returns the name of a type along with the path of the namespaces containing it, e.g.
+    /// `alloc::vec::Vec<u8, alloc::alloc::Global>`, as Rust compilers don't include the path in
+    /// the names of structs and enums.
+    pub fn get_qualified_type_name(
+        &self,
+        id: TypeId,
+    ) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        self.rent(|parsed| parsed.get_qualified_type_name(id))
+    }
+
+    /// Finds a struct, union or enum by its qualified name, e.g.
+    /// `alloc::vec::Vec<u8, alloc::alloc::Global>`. Only types which are used by the program are
+    /// described by the debug info.
+    pub fn find_type(
+        &self,
+        qualified_name: &str,
+    ) -> Result<Option<TypeId>, Box<dyn std::error::Error>> {
+        self.rent(|parsed| parsed.find_type(qualified_name))
+    }
+
+    /// Returns the frame base of the function containing `addr`, along with the index of the
+    /// unit of the function which is required to evaluate it.
+    pub(crate) fn get_frame_base(
+        &self,
+        addr: usize,
+    ) -> Result<Option<(VariableLocation, usize)>, Box<dyn std::error::Error>> {
+        self.rent(|parsed| parsed.get_frame_base(addr))
+    }
+
+    /// Returns an expression that computes the value `register` had when the function returning
+    /// to `return_addr` has been called. It has to be evaluated in the frame of the caller.
+    pub(crate) fn get_call_site_value(
+        &self,
+        return_addr: usize,
+        register: u16,
+    ) -> Result<Option<UnitExpression>, Box<dyn std::error::Error>> {
+        self.rent(|parsed| parsed.get_call_site_value(return_addr, register))
+    }
+
+    /// Returns an address from `.debug_addr`, as referred to by `DW_OP_addrx`.
+    pub(crate) fn get_indexed_address(
+        &self,
+        unit: usize,
+        index: gimli::DebugAddrIndex<usize>,
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        self.rent(|parsed| parsed.get_indexed_address(unit, index))
+    }
+
+    pub(crate) fn get_unit_encoding(&self, unit: usize) -> gimli::Encoding {
+        self.rent(|parsed| parsed.get_unit_encoding(unit))
+    }
+
+    /// Returns the size and the alignment of the thread-local storage block of the executable.
+    pub(crate) fn get_tls_block(&self) -> Option<(usize, usize)> {
+        self.rent(|parsed| parsed.get_tls_block())
+    }
+
+    /// Returns the address the start of the file is loaded at unless it is relocated, which is
+    /// usually zero for position-independent executables and shared libraries.
+    pub(crate) fn get_load_address(&self) -> u64 {
+        self.rent(|parsed| parsed.get_load_address())
+    }
+
+    /// Returns the source location of the code at `addr`.
+    pub fn get_address_location(&self, addr: usize) -> Option<SourceLocation> {
+        self.rent(|parsed| parsed.get_address_location(addr))
+    }
+
+    /// Returns the addresses of the code of a source line, which can be used to set breakpoints.
+    /// `file` can also be a trailing part of the path, e.g. `src/main.rs` or `main.rs`.
+    pub fn get_line_addresses(&self, file: &str, line: u64) -> Vec<usize> {
+        self.rent(|parsed| parsed.get_line_addresses(file, line))
+    }
+
+    pub(crate) fn get_line_row(&self, addr: usize) -> Option<LineRow> {
+        self.rent(|parsed| parsed.get_line_row(addr))
+    }
+
+    /// Unwinds one stack frame using the call frame information in `.eh_frame` and
+    /// `.debug_frame`. `regs` are the registers of the frame executing the code at `addr`.
+    /// Returns the canonical frame address of the frame and the registers of its caller, or
+    /// `None` if there is no call frame information for `addr`.
+    pub(crate) fn unwind_frame(
+        &self,
+        addr: usize,
+        regs: &UnwindRegisters,
+        read_memory: &mut dyn FnMut(usize) -> Result<u64, Box<dyn std::error::Error>>,
+    ) -> Result<Option<(usize, UnwindRegisters)>, Box<dyn std::error::Error>> {
+        self.rent(|parsed| parsed.unwind_frame(addr, regs, read_memory))
+    }
 }
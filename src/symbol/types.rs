@@ -0,0 +1,649 @@
+// Types of variables, read from the type entries in `.debug_info`, e.g. `DW_TAG_base_type` or
+// `DW_TAG_structure_type`.
+
+use gimli::Reader as _;
+
+use super::{function::resolve_ref, AttributeValue, Reader};
+
+/// Identifies a type described by the debug info, see
+/// [`Dwarf::get_type`](super::Dwarf::get_type).
+/// Types refer to other types by their id, which allows to describe recursive types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TypeId {
+    pub(crate) unit: usize,
+    pub(crate) offset: gimli::UnitOffset,
+}
+
+/// A type described by the debug info.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Type {
+    /// The name of the type, e.g. `u32`, `Option<&u8>` or `&mycrate::Point`.
+    /// Rust compilers don't include the path in the names of structs and enums.
+    pub name: Option<String>,
+    /// The size of the type in bytes.
+    pub size: Option<u64>,
+    /// The alignment of the type in bytes.
+    pub alignment: Option<u64>,
+    pub kind: TypeKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeKind {
+    /// A primitive type, e.g. an integer.
+    Base(BaseEncoding),
+    /// A struct, tuple or Rust enum. Rust enums are described by a variant part.
+    Struct(StructType),
+    /// A union.
+    Union(StructType),
+    /// A C-like enum.
+    Enum(EnumType),
+    /// A pointer or a reference, including Rust references and `Box`es.
+    Pointer {
+        pointee: Option<TypeId>,
+        is_reference: bool,
+    },
+    /// An array with the given number of elements in each dimension, outermost first.
+    /// The number of elements is unknown for arrays of unspecified size, e.g. `int a[]` in C.
+    Array {
+        element: Option<TypeId>,
+        counts: Vec<Option<u64>>,
+    },
+    /// The type of functions, including function pointers.
+    Subroutine {
+        return_type: Option<TypeId>,
+        parameters: Vec<Option<TypeId>>,
+    },
+    /// Another name for a type, e.g. a C `typedef`.
+    Typedef { target: Option<TypeId> },
+    /// A type qualified with `const`, `volatile`, `restrict` or `_Atomic` in C.
+    Qualified { target: Option<TypeId> },
+    /// A type without a description, e.g. `void` in C.
+    Unspecified,
+}
+
+/// How values of primitive types are encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BaseEncoding {
+    Boolean,
+    Signed,
+    Unsigned,
+    Float,
+    /// A Unicode character, e.g. a Rust `char`.
+    Char,
+    SignedChar,
+    UnsignedChar,
+    /// An encoding not covered above, e.g. a complex number.
+    Other(gimli::DwAte),
+}
+
+/// The description of a struct, tuple, union or Rust enum.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructType {
+    pub fields: Vec<Field>,
+    /// The parameters of generic types, e.g. `T` of `Vec<T>`.
+    pub template_params: Vec<TemplateParam>,
+    /// The variants of a Rust enum.
+    pub variant_part: Option<VariantPart>,
+}
+
+/// A member of a struct, tuple or union. Fields of tuples are named `__0`, `__1` and so on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    pub name: Option<String>,
+    pub ty: Option<TypeId>,
+    /// The offset of the field in bytes from the start of the containing type.
+    pub offset: Option<u64>,
+    /// `true` for fields generated by the compiler, e.g. enum discriminants.
+    pub is_artificial: bool,
+}
+
+/// A type parameter of a generic type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateParam {
+    pub name: Option<String>,
+    pub ty: Option<TypeId>,
+}
+
+/// The variants of a Rust enum, of which only one is active, as selected by the discriminant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VariantPart {
+    /// The field holding the discriminant. Enums with a single variant don't have one.
+    /// The discriminant is stored in an otherwise invalid value of a field of a variant for
+    /// niche-optimized enums like `Option<&T>`.
+    pub discriminant: Option<Field>,
+    pub variants: Vec<Variant>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Variant {
+    /// The value of the discriminant which selects this variant. `None` for the default
+    /// variant, which is active if no other variant matches.
+    pub discr_value: Option<u64>,
+    /// The fields of the variant, which is usually a single field containing a struct named
+    /// after the variant.
+    pub fields: Vec<Field>,
+}
+
+/// A C-like enum.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnumType {
+    /// The integer type used to store the enum.
+    pub underlying: Option<TypeId>,
+    pub enumerators: Vec<Enumerator>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Enumerator {
+    pub name: Option<String>,
+    pub value: i128,
+}
+
+/// Returns the id of the type referred to by a `DW_AT_type` attribute or a similar reference.
+pub(super) fn type_id(
+    units: &[gimli::Unit<Reader>],
+    unit_index: usize,
+    reference: AttributeValue,
+) -> Option<TypeId> {
+    let (unit, offset) = resolve_ref(units, unit_index, reference)?;
+    Some(TypeId { unit, offset })
+}
+
+/// Returns the type referred to by the `DW_AT_containing_type` attribute of a type, e.g. the
+/// concrete type of a Rust vtable.
+pub(super) fn containing_type(
+    units: &[gimli::Unit<Reader>],
+    id: TypeId,
+) -> Result<Option<TypeId>, Box<dyn std::error::Error>> {
+    let entry = units[id.unit].entry(id.offset)?;
+    Ok(entry
+        .attr_value(gimli::DW_AT_containing_type)?
+        .and_then(|reference| type_id(units, id.unit, reference)))
+}
+
+/// Reads the description of a type.
+pub(super) fn parse_type(
+    dwarf: &gimli::Dwarf<Reader>,
+    units: &[gimli::Unit<Reader>],
+    id: TypeId,
+) -> Result<Type, Box<dyn std::error::Error>> {
+    let unit = &units[id.unit];
+    let entry = unit.entry(id.offset)?;
+
+    let name = entry_name(dwarf, unit, &entry)?;
+    let size = entry
+        .attr_value(gimli::DW_AT_byte_size)?
+        .and_then(|value| value.udata_value());
+    let alignment = entry
+        .attr_value(gimli::DW_AT_alignment)?
+        .and_then(|value| value.udata_value());
+    let target = entry_type(units, id.unit, &entry)?;
+
+    let kind = match entry.tag() {
+        gimli::DW_TAG_base_type => {
+            let encoding = match entry.attr_value(gimli::DW_AT_encoding)? {
+                Some(gimli::AttributeValue::Encoding(encoding)) => encoding,
+                _ => return Err("Base type without an encoding".into()),
+            };
+            TypeKind::Base(match encoding {
+                gimli::DW_ATE_boolean => BaseEncoding::Boolean,
+                gimli::DW_ATE_signed => BaseEncoding::Signed,
+                gimli::DW_ATE_unsigned => BaseEncoding::Unsigned,
+                gimli::DW_ATE_float => BaseEncoding::Float,
+                gimli::DW_ATE_UTF => BaseEncoding::Char,
+                gimli::DW_ATE_signed_char => BaseEncoding::SignedChar,
+                gimli::DW_ATE_unsigned_char => BaseEncoding::UnsignedChar,
+                encoding => BaseEncoding::Other(encoding),
+            })
+        }
+        gimli::DW_TAG_structure_type | gimli::DW_TAG_class_type => {
+            TypeKind::Struct(struct_type(dwarf, units, id)?)
+        }
+        gimli::DW_TAG_union_type => TypeKind::Union(struct_type(dwarf, units, id)?),
+        gimli::DW_TAG_enumeration_type => TypeKind::Enum(enum_type(dwarf, units, id, target)?),
+        gimli::DW_TAG_pointer_type => TypeKind::Pointer {
+            pointee: target,
+            is_reference: false,
+        },
+        gimli::DW_TAG_reference_type | gimli::DW_TAG_rvalue_reference_type => TypeKind::Pointer {
+            pointee: target,
+            is_reference: true,
+        },
+        gimli::DW_TAG_array_type => {
+            let mut counts = Vec::new();
+            let mut tree = unit.entries_tree(Some(id.offset))?;
+            let mut children = tree.root()?.children();
+            while let Some(child) = children.next()? {
+                let entry = child.entry();
+                if entry.tag() == gimli::DW_TAG_subrange_type {
+                    counts.push(subrange_count(entry)?);
+                }
+            }
+            TypeKind::Array {
+                element: target,
+                counts,
+            }
+        }
+        gimli::DW_TAG_subroutine_type => {
+            let mut parameters = Vec::new();
+            let mut tree = unit.entries_tree(Some(id.offset))?;
+            let mut children = tree.root()?.children();
+            while let Some(child) = children.next()? {
+                let entry = child.entry();
+                if entry.tag() == gimli::DW_TAG_formal_parameter {
+                    parameters.push(entry_type(units, id.unit, entry)?);
+                }
+            }
+            TypeKind::Subroutine {
+                return_type: target,
+                parameters,
+            }
+        }
+        gimli::DW_TAG_typedef => TypeKind::Typedef { target },
+        gimli::DW_TAG_const_type
+        | gimli::DW_TAG_volatile_type
+        | gimli::DW_TAG_restrict_type
+        | gimli::DW_TAG_atomic_type => TypeKind::Qualified { target },
+        gimli::DW_TAG_unspecified_type => TypeKind::Unspecified,
+        tag => return Err(format!("Unsupported type entry: {}", tag).into()),
+    };
+
+    // The size of arrays and pointers is usually implied.
+    let size = match (size, &kind) {
+        (None, TypeKind::Array { .. }) | (None, TypeKind::Pointer { .. }) => type_size(units, id)?,
+        _ => size,
+    };
+
+    Ok(Type {
+        name,
+        size,
+        alignment,
+        kind,
+    })
+}
+
+/// Returns the size of a type in bytes, computing it for types which don't specify it, e.g.
+/// arrays.
+fn type_size(
+    units: &[gimli::Unit<Reader>],
+    id: TypeId,
+) -> Result<Option<u64>, Box<dyn std::error::Error>> {
+    let mut id = id;
+    let mut multiplier = 1;
+    // Malformed debug info could contain a cycle of references.
+    for _ in 0..16 {
+        let unit = &units[id.unit];
+        let entry = unit.entry(id.offset)?;
+        if let Some(size) = entry.attr_value(gimli::DW_AT_byte_size)? {
+            return Ok(size.udata_value().map(|size| size * multiplier));
+        }
+        match entry.tag() {
+            gimli::DW_TAG_pointer_type
+            | gimli::DW_TAG_reference_type
+            | gimli::DW_TAG_rvalue_reference_type => {
+                return Ok(Some(u64::from(unit.encoding().address_size) * multiplier))
+            }
+            gimli::DW_TAG_array_type => {
+                let mut tree = unit.entries_tree(Some(id.offset))?;
+                let mut children = tree.root()?.children();
+                while let Some(child) = children.next()? {
+                    let entry = child.entry();
+                    if entry.tag() == gimli::DW_TAG_subrange_type {
+                        match subrange_count(entry)? {
+                            Some(count) => multiplier *= count,
+                            None => return Ok(None),
+                        }
+                    }
+                }
+            }
+            gimli::DW_TAG_typedef
+            | gimli::DW_TAG_const_type
+            | gimli::DW_TAG_volatile_type
+            | gimli::DW_TAG_restrict_type
+            | gimli::DW_TAG_atomic_type => {}
+            _ => return Ok(None),
+        }
+        id = match entry_type(units, id.unit, &entry)? {
+            Some(target) => target,
+            None => return Ok(None),
+        };
+    }
+    Ok(None)
+}
+
+fn struct_type(
+    dwarf: &gimli::Dwarf<Reader>,
+    units: &[gimli::Unit<Reader>],
+    id: TypeId,
+) -> Result<StructType, Box<dyn std::error::Error>> {
+    let unit = &units[id.unit];
+    let mut fields = Vec::new();
+    let mut template_params = Vec::new();
+    let mut variant_part = None;
+
+    let mut tree = unit.entries_tree(Some(id.offset))?;
+    let mut children = tree.root()?.children();
+    while let Some(child) = children.next()? {
+        let entry = child.entry();
+        match entry.tag() {
+            // Static members are not part of the values of the type.
+            gimli::DW_TAG_member if entry.attr(gimli::DW_AT_external)?.is_none() => {
+                fields.push(field(dwarf, units, id.unit, entry)?);
+            }
+            gimli::DW_TAG_template_type_parameter => template_params.push(TemplateParam {
+                name: entry_name(dwarf, unit, entry)?,
+                ty: entry_type(units, id.unit, entry)?,
+            }),
+            gimli::DW_TAG_variant_part => {
+                variant_part = Some(parse_variant_part(dwarf, units, id.unit, child)?)
+            }
+            _ => {}
+        }
+    }
+
+    Ok(StructType {
+        fields,
+        template_params,
+        variant_part,
+    })
+}
+
+fn parse_variant_part(
+    dwarf: &gimli::Dwarf<Reader>,
+    units: &[gimli::Unit<Reader>],
+    unit_index: usize,
+    node: gimli::EntriesTreeNode<Reader>,
+) -> Result<VariantPart, Box<dyn std::error::Error>> {
+    let discr_offset = match node.entry().attr_value(gimli::DW_AT_discr)? {
+        Some(gimli::AttributeValue::UnitRef(offset)) => Some(offset),
+        _ => None,
+    };
+    let mut discriminant = None;
+    let mut variants = Vec::new();
+
+    let mut children = node.children();
+    while let Some(child) = children.next()? {
+        let entry = child.entry();
+        match entry.tag() {
+            gimli::DW_TAG_member if Some(entry.offset()) == discr_offset => {
+                discriminant = Some(field(dwarf, units, unit_index, entry)?);
+            }
+            gimli::DW_TAG_variant => {
+                // Negative values are kept in two's complement, as discriminants are compared at
+                // the size of the tag.
+                let discr_value = match entry.attr_value(gimli::DW_AT_discr_value)? {
+                    Some(gimli::AttributeValue::Sdata(value)) => Some(value as u64),
+                    Some(value) => value.udata_value(),
+                    None => None,
+                };
+                let mut fields = Vec::new();
+                let mut variant_children = child.children();
+                while let Some(variant_child) = variant_children.next()? {
+                    let entry = variant_child.entry();
+                    if entry.tag() == gimli::DW_TAG_member {
+                        fields.push(field(dwarf, units, unit_index, entry)?);
+                    }
+                }
+                variants.push(Variant {
+                    discr_value,
+                    fields,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    Ok(VariantPart {
+        discriminant,
+        variants,
+    })
+}
+
+fn enum_type(
+    dwarf: &gimli::Dwarf<Reader>,
+    units: &[gimli::Unit<Reader>],
+    id: TypeId,
+    underlying: Option<TypeId>,
+) -> Result<EnumType, Box<dyn std::error::Error>> {
+    let unit = &units[id.unit];
+    let mut enumerators = Vec::new();
+
+    let mut tree = unit.entries_tree(Some(id.offset))?;
+    let mut children = tree.root()?.children();
+    while let Some(child) = children.next()? {
+        let entry = child.entry();
+        if entry.tag() != gimli::DW_TAG_enumerator {
+            continue;
+        }
+        let value = match entry.attr_value(gimli::DW_AT_const_value)? {
+            Some(gimli::AttributeValue::Sdata(value)) => i128::from(value),
+            Some(value) => match value.udata_value() {
+                Some(value) => i128::from(value),
+                None => continue,
+            },
+            None => continue,
+        };
+        enumerators.push(Enumerator {
+            name: entry_name(dwarf, unit, entry)?,
+            value,
+        });
+    }
+
+    Ok(EnumType {
+        underlying,
+        enumerators,
+    })
+}
+
+fn field(
+    dwarf: &gimli::Dwarf<Reader>,
+    units: &[gimli::Unit<Reader>],
+    unit_index: usize,
+    entry: &gimli::DebuggingInformationEntry<Reader>,
+) -> Result<Field, Box<dyn std::error::Error>> {
+    // The location of virtual base classes in C++ is computed by an expression.
+    let offset = entry
+        .attr_value(gimli::DW_AT_data_member_location)?
+        .and_then(|value| value.udata_value());
+    let is_artificial = matches!(
+        entry.attr_value(gimli::DW_AT_artificial)?,
+        Some(gimli::AttributeValue::Flag(true))
+    );
+    Ok(Field {
+        name: entry_name(dwarf, &units[unit_index], entry)?,
+        ty: entry_type(units, unit_index, entry)?,
+        offset,
+        is_artificial,
+    })
+}
+
+/// Returns the number of elements of a dimension of an array.
+fn subrange_count(
+    entry: &gimli::DebuggingInformationEntry<Reader>,
+) -> Result<Option<u64>, Box<dyn std::error::Error>> {
+    if let Some(count) = entry.attr_value(gimli::DW_AT_count)? {
+        return Ok(count.udata_value());
+    }
+    let lower_bound = entry
+        .attr_value(gimli::DW_AT_lower_bound)?
+        .and_then(|value| value.udata_value())
+        .unwrap_or(0);
+    Ok(entry
+        .attr_value(gimli::DW_AT_upper_bound)?
+        .and_then(|value| value.udata_value())
+        .and_then(|upper_bound| upper_bound.checked_add(1)?.checked_sub(lower_bound)))
+}
+
+/// Returns the name of a type prefixed with the path of the namespaces containing it.
+pub(super) fn qualified_type_name(
+    dwarf: &gimli::Dwarf<Reader>,
+    units: &[gimli::Unit<Reader>],
+    id: TypeId,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let unit = &units[id.unit];
+    let name = match entry_name(dwarf, unit, &unit.entry(id.offset)?)? {
+        Some(name) => name,
+        None => return Ok(None),
+    };
+    let mut path = Vec::new();
+    let mut tree = unit.entries_tree(None)?;
+    namespace_path(dwarf, unit, tree.root()?, id.offset, &mut path)?;
+    path.push(name);
+    Ok(Some(path.join("::")))
+}
+
+/// Collects the names of the namespaces within `node` which contain the entry at `offset`,
+/// outermost first. Returns `false` if the entry isn't found within namespaces.
+fn namespace_path(
+    dwarf: &gimli::Dwarf<Reader>,
+    unit: &gimli::Unit<Reader>,
+    node: gimli::EntriesTreeNode<Reader>,
+    offset: gimli::UnitOffset,
+    path: &mut Vec<String>,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let mut children = node.children();
+    while let Some(child) = children.next()? {
+        let entry = child.entry();
+        if entry.offset() == offset {
+            return Ok(true);
+        }
+        // Entries are ordered by their offsets, so later ones can't contain the entry.
+        if entry.offset() > offset {
+            return Ok(false);
+        }
+        if entry.tag() == gimli::DW_TAG_namespace {
+            path.push(entry_name(dwarf, unit, entry)?.unwrap_or_default());
+            if namespace_path(dwarf, unit, child, offset, path)? {
+                return Ok(true);
+            }
+            path.pop();
+        }
+    }
+    Ok(false)
+}
+
+/// Finds a struct, union or enum by its name prefixed with the path of its namespaces.
+pub(super) fn find_type(
+    dwarf: &gimli::Dwarf<Reader>,
+    units: &[gimli::Unit<Reader>],
+    qualified_name: &str,
+) -> Result<Option<TypeId>, Box<dyn std::error::Error>> {
+    for (unit_index, unit) in units.iter().enumerate() {
+        let mut tree = unit.entries_tree(None)?;
+        if let Some(offset) = find_in_namespace(dwarf, unit, tree.root()?, qualified_name)? {
+            return Ok(Some(TypeId {
+                unit: unit_index,
+                offset,
+            }));
+        }
+    }
+    Ok(None)
+}
+
+/// Finds the type `name` within `node`, descending only into the namespaces `name` starts with.
+fn find_in_namespace(
+    dwarf: &gimli::Dwarf<Reader>,
+    unit: &gimli::Unit<Reader>,
+    node: gimli::EntriesTreeNode<Reader>,
+    name: &str,
+) -> Result<Option<gimli::UnitOffset>, Box<dyn std::error::Error>> {
+    let mut children = node.children();
+    while let Some(child) = children.next()? {
+        let entry = child.entry();
+        match entry.tag() {
+            gimli::DW_TAG_namespace => {
+                let namespace = entry_name(dwarf, unit, entry)?.unwrap_or_default();
+                let rest = name
+                    .strip_prefix(namespace.as_str())
+                    .and_then(|rest| rest.strip_prefix("::"));
+                if let Some(rest) = rest {
+                    if let Some(offset) = find_in_namespace(dwarf, unit, child, rest)? {
+                        return Ok(Some(offset));
+                    }
+                }
+            }
+            gimli::DW_TAG_structure_type
+            | gimli::DW_TAG_union_type
+            | gimli::DW_TAG_enumeration_type
+                if entry_name(dwarf, unit, entry)?.as_deref() == Some(name) =>
+            {
+                return Ok(Some(entry.offset()));
+            }
+            _ => {}
+        }
+    }
+    Ok(None)
+}
+
+fn entry_name(
+    dwarf: &gimli::Dwarf<Reader>,
+    unit: &gimli::Unit<Reader>,
+    entry: &gimli::DebuggingInformationEntry<Reader>,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    match entry.attr_value(gimli::DW_AT_name)? {
+        Some(value) => Ok(Some(
+            dwarf
+                .attr_string(unit, value)?
+                .to_string_lossy()?
+                .into_owned(),
+        )),
+        None => Ok(None),
+    }
+}
+
+fn entry_type(
+    units: &[gimli::Unit<Reader>],
+    unit_index: usize,
+    entry: &gimli::DebuggingInformationEntry<Reader>,
+) -> Result<Option<TypeId>, Box<dyn std::error::Error>> {
+    Ok(entry
+        .attr_value(gimli::DW_AT_type)?
+        .and_then(|reference| type_id(units, unit_index, reference)))
+}
+
+/// Returns the name of a type.
+/// Rust compilers name all types, C pointer and qualified types are named after the type they
+/// refer to.
+pub(super) fn type_name(
+    dwarf: &gimli::Dwarf<Reader>,
+    units: &[gimli::Unit<Reader>],
+    id: TypeId,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let TypeId {
+        unit: mut unit_index,
+        mut offset,
+    } = id;
+
+    let mut prefixes: Vec<&str> = Vec::new();
+    let mut suffixes: Vec<&str> = Vec::new();
+    let qualified = |prefixes: &[&str], name: &str, suffixes: &[&str]| {
+        let mut type_name = prefixes.concat();
+        type_name.push_str(name);
+        type_name.extend(suffixes.iter().rev().copied());
+        type_name
+    };
+    // Malformed debug info could contain a cycle of references.
+    for _ in 0..16 {
+        let unit = &units[unit_index];
+        let entry = unit.entry(offset)?;
+        if let Some(name) = entry_name(dwarf, unit, &entry)? {
+            return Ok(Some(qualified(&prefixes, &name, &suffixes)));
+        }
+
+        match entry.tag() {
+            gimli::DW_TAG_pointer_type => suffixes.push(" *"),
+            gimli::DW_TAG_reference_type => suffixes.push(" &"),
+            gimli::DW_TAG_const_type => prefixes.push("const "),
+            gimli::DW_TAG_volatile_type => prefixes.push("volatile "),
+            _ => return Ok(None),
+        }
+        match entry.attr_value(gimli::DW_AT_type)? {
+            Some(reference) => match resolve_ref(units, unit_index, reference) {
+                Some(target) => (unit_index, offset) = target,
+                None => return Ok(None),
+            },
+            // Pointers without a type point to `void`.
+            None => return Ok(Some(qualified(&prefixes, "void", &suffixes))),
+        }
+    }
+    Ok(None)
+}
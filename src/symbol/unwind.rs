@@ -0,0 +1,251 @@
+// Call frame information from `.eh_frame` and `.debug_frame`, which describes how to find the
+// registers of the caller of a function at any point of its code.
+
+use gimli::UnwindSection;
+use std::ops::Range;
+
+use super::Reader;
+
+/// The number of x86_64 registers which are recovered when unwinding the stack, in the order
+/// of their DWARF register numbers: `rax`, `rdx`, `rcx`, `rbx`, `rsi`, `rdi`, `rbp`, `rsp`,
+/// `r8`-`r15`, and the return address.
+pub const UNWIND_REGISTERS: usize = 17;
+
+/// Values of registers in a stack frame, indexed by DWARF register number.
+/// `None` if the value is unknown, e.g. because the register is not preserved across calls.
+pub type UnwindRegisters = [Option<u64>; UNWIND_REGISTERS];
+
+/// The DWARF register number of `rsp`.
+pub(crate) const RSP: usize = 7;
+/// The DWARF register number of the return address.
+pub(crate) const RETURN_ADDRESS: usize = 16;
+
+#[derive(Clone, Copy)]
+enum Section {
+    EhFrame,
+    DebugFrame,
+}
+
+/// The call frame information of both `.eh_frame` and `.debug_frame`.
+pub(crate) struct CallFrameInfo<'a> {
+    eh_frame: gimli::EhFrame<Reader<'a>>,
+    eh_frame_bases: gimli::BaseAddresses,
+    debug_frame: gimli::DebugFrame<Reader<'a>>,
+    debug_frame_bases: gimli::BaseAddresses,
+    // Code ranges covered by the FDEs of both sections, sorted by address. Looking up the FDE of
+    // an address directly in a section requires parsing all FDEs before it.
+    fdes: Vec<(Range<usize>, Section, usize)>,
+}
+
+impl<'a> CallFrameInfo<'a> {
+    pub(super) fn new(
+        mut eh_frame: gimli::EhFrame<Reader<'a>>,
+        eh_frame_bases: gimli::BaseAddresses,
+        mut debug_frame: gimli::DebugFrame<Reader<'a>>,
+        address_size: u8,
+    ) -> Result<CallFrameInfo<'a>, Box<dyn std::error::Error>> {
+        eh_frame.set_address_size(address_size);
+        debug_frame.set_address_size(address_size);
+        let debug_frame_bases = gimli::BaseAddresses::default();
+
+        let mut fdes = Vec::new();
+        let mut entries = eh_frame.entries(&eh_frame_bases);
+        while let Some(entry) = entries.next()? {
+            if let gimli::CieOrFde::Fde(partial) = entry {
+                let fde = partial.parse(gimli::EhFrame::cie_from_offset)?;
+                fdes.push((fde_range(&fde), Section::EhFrame, fde.offset()));
+            }
+        }
+        let mut entries = debug_frame.entries(&debug_frame_bases);
+        while let Some(entry) = entries.next()? {
+            if let gimli::CieOrFde::Fde(partial) = entry {
+                let fde = partial.parse(gimli::DebugFrame::cie_from_offset)?;
+                fdes.push((fde_range(&fde), Section::DebugFrame, fde.offset()));
+            }
+        }
+        // Discarded functions are relocated to address 0 by the linker.
+        fdes.retain(|(range, _, _)| range.start != 0 && !range.is_empty());
+        fdes.sort_by_key(|(range, _, _)| range.start);
+
+        Ok(CallFrameInfo {
+            eh_frame,
+            eh_frame_bases,
+            debug_frame,
+            debug_frame_bases,
+            fdes,
+        })
+    }
+
+    /// Finds the unwind table row of the code at `addr`.
+    fn find_row(
+        &self,
+        addr: usize,
+    ) -> Result<Option<gimli::UnwindTableRow<Reader<'a>>>, Box<dyn std::error::Error>> {
+        let index = self
+            .fdes
+            .partition_point(|(range, _, _)| range.start <= addr);
+        let (range, section, offset) = match index.checked_sub(1).map(|index| &self.fdes[index]) {
+            Some(fde) => fde,
+            None => return Ok(None),
+        };
+        if !range.contains(&addr) {
+            return Ok(None);
+        }
+
+        let mut ctx = gimli::UninitializedUnwindContext::new();
+        let row = match section {
+            Section::EhFrame => self
+                .eh_frame
+                .fde_from_offset(
+                    &self.eh_frame_bases,
+                    gimli::EhFrameOffset(*offset),
+                    gimli::EhFrame::cie_from_offset,
+                )?
+                .unwind_info_for_address(
+                    &self.eh_frame,
+                    &self.eh_frame_bases,
+                    &mut ctx,
+                    addr as u64,
+                )?,
+            Section::DebugFrame => self
+                .debug_frame
+                .fde_from_offset(
+                    &self.debug_frame_bases,
+                    gimli::DebugFrameOffset(*offset),
+                    gimli::DebugFrame::cie_from_offset,
+                )?
+                .unwind_info_for_address(
+                    &self.debug_frame,
+                    &self.debug_frame_bases,
+                    &mut ctx,
+                    addr as u64,
+                )?,
+        };
+        Ok(Some(row))
+    }
+
+    /// Computes the canonical frame address of the frame executing the code at `addr` with the
+    /// given register values, along with the register values of its caller.
+    /// Returns `None` if there is no call frame information for `addr`.
+    pub(crate) fn unwind(
+        &self,
+        addr: usize,
+        regs: &UnwindRegisters,
+        read_memory: &mut dyn FnMut(usize) -> Result<u64, Box<dyn std::error::Error>>,
+    ) -> Result<Option<(usize, UnwindRegisters)>, Box<dyn std::error::Error>> {
+        let row = match self.find_row(addr)? {
+            Some(row) => row,
+            None => return Ok(None),
+        };
+
+        let cfa = match row.cfa() {
+            gimli::CfaRule::RegisterAndOffset { register, offset } => {
+                let value = register_value(regs, *register)?;
+                (value as i64).wrapping_add(*offset) as u64
+            }
+            gimli::CfaRule::Expression(expr) => evaluate(expr, None, regs, read_memory)?,
+        };
+
+        let mut caller_regs = [None; UNWIND_REGISTERS];
+        // The stack pointer of the caller is the CFA by definition, unless stated otherwise.
+        caller_regs[RSP] = Some(cfa);
+        for (register, caller_reg) in caller_regs.iter_mut().enumerate() {
+            let rule = row.register(gimli::Register(register as u16));
+            *caller_reg = match rule {
+                // The callee-saved registers are preserved by default on x86_64, which is
+                // stated explicitly in `.debug_frame` only.
+                gimli::RegisterRule::Undefined if register == RSP => Some(cfa),
+                gimli::RegisterRule::Undefined => match register {
+                    3 | 6 | 12..=15 => regs[register],
+                    _ => None,
+                },
+                gimli::RegisterRule::SameValue => regs[register],
+                gimli::RegisterRule::Offset(offset) => {
+                    Some(read_memory((cfa as i64).wrapping_add(offset) as usize)?)
+                }
+                gimli::RegisterRule::ValOffset(offset) => {
+                    Some((cfa as i64).wrapping_add(offset) as u64)
+                }
+                gimli::RegisterRule::Register(other) => {
+                    regs.get(other.0 as usize).copied().flatten()
+                }
+                gimli::RegisterRule::Expression(expr) => {
+                    let addr = evaluate(&expr, Some(cfa), regs, read_memory)?;
+                    Some(read_memory(addr as usize)?)
+                }
+                gimli::RegisterRule::ValExpression(expr) => {
+                    Some(evaluate(&expr, Some(cfa), regs, read_memory)?)
+                }
+                gimli::RegisterRule::Architectural => None,
+            };
+        }
+
+        Ok(Some((cfa as usize, caller_regs)))
+    }
+}
+
+fn fde_range(fde: &gimli::FrameDescriptionEntry<Reader>) -> Range<usize> {
+    fde.initial_address() as usize..(fde.initial_address() + fde.len()) as usize
+}
+
+fn register_value(
+    regs: &UnwindRegisters,
+    register: gimli::Register,
+) -> Result<u64, Box<dyn std::error::Error>> {
+    regs.get(register.0 as usize)
+        .copied()
+        .flatten()
+        .ok_or_else(|| format!("The value of register {} is unknown", register.0).into())
+}
+
+/// Evaluates a DWARF expression of a CFA or register rule, which can only refer to registers
+/// and memory. Register rules start with the CFA on the stack.
+fn evaluate(
+    expr: &gimli::Expression<Reader>,
+    cfa: Option<u64>,
+    regs: &UnwindRegisters,
+    read_memory: &mut dyn FnMut(usize) -> Result<u64, Box<dyn std::error::Error>>,
+) -> Result<u64, Box<dyn std::error::Error>> {
+    let encoding = gimli::Encoding {
+        format: gimli::Format::Dwarf32,
+        version: 4,
+        address_size: 8,
+    };
+    let mut eval = expr.clone().evaluation(encoding);
+    if let Some(cfa) = cfa {
+        eval.set_initial_value(cfa);
+    }
+
+    let mut result = eval.evaluate()?;
+    loop {
+        result = match result {
+            gimli::EvaluationResult::Complete => break,
+            gimli::EvaluationResult::RequiresMemory { address, size, .. } => {
+                let value = read_memory(address as usize)?;
+                let value = if size >= 8 {
+                    value
+                } else {
+                    value & ((1 << (size * 8)) - 1)
+                };
+                eval.resume_with_memory(gimli::Value::Generic(value))?
+            }
+            gimli::EvaluationResult::RequiresRegister { register, .. } => {
+                let value = register_value(regs, register)?;
+                eval.resume_with_register(gimli::Value::Generic(value))?
+            }
+            result => {
+                return Err(
+                    format!("Unsupported unwind expression requirement: {:?}", result).into(),
+                )
+            }
+        };
+    }
+
+    match eval.result().as_slice() {
+        [gimli::Piece {
+            location: gimli::Location::Address { address },
+            ..
+        }] => Ok(*address),
+        pieces => Err(format!("Unexpected unwind expression result: {:?}", pieces).into()),
+    }
+}
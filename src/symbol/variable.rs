@@ -0,0 +1,302 @@
+// Local variables and parameters of functions, read from the `DW_TAG_variable` and
+// `DW_TAG_formal_parameter` entries in `.debug_info`.
+
+use gimli::Reader as _;
+use std::ops::Range;
+
+use super::{
+    function::{entry_ranges, find_attr},
+    line, types, AttributeValue, Reader, TypeId,
+};
+
+/// Whether a variable is a parameter or a local variable of a function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VariableKind {
+    Parameter,
+    Local,
+}
+
+/// Where the value of a variable is stored, as a DWARF location description.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VariableLocation {
+    /// A location expression valid wherever the variable is in scope.
+    Expression(Vec<u8>),
+    /// Location expressions along with the address ranges in which they are valid, e.g. because
+    /// the variable is moved between registers. The variable is optimized out at addresses
+    /// outside of all ranges.
+    List(Vec<(Range<usize>, Vec<u8>)>),
+}
+
+impl VariableLocation {
+    /// Returns the location expression which is valid when executing the code at `addr`.
+    pub fn expression_at(&self, addr: usize) -> Option<&[u8]> {
+        match self {
+            VariableLocation::Expression(expr) => Some(expr),
+            VariableLocation::List(list) => list
+                .iter()
+                .find(|(range, _)| range.contains(&addr))
+                .map(|(_, expr)| &expr[..]),
+        }
+    }
+}
+
+/// A DWARF expression along with the index of the unit it belongs to, which determines how it
+/// is encoded.
+pub(crate) struct UnitExpression {
+    pub(crate) expr: Vec<u8>,
+    pub(crate) unit: usize,
+}
+
+/// A parameter or a local variable of a function, see
+/// [`Dwarf::get_address_variables`](super::Dwarf::get_address_variables).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Variable {
+    name: Option<String>,
+    kind: VariableKind,
+    type_id: Option<TypeId>,
+    type_name: Option<String>,
+    location: Option<VariableLocation>,
+    decl_file: Option<String>,
+    decl_line: Option<u64>,
+    // Location of the entry of this variable.
+    pub(crate) unit: usize,
+    pub(crate) offset: gimli::UnitOffset,
+}
+
+impl Variable {
+    /// Returns the name of the variable as written in the source code.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns whether the variable is a parameter or a local variable.
+    pub fn kind(&self) -> VariableKind {
+        self.kind
+    }
+
+    /// Returns the type of the variable, which can be looked up with
+    /// [`Dwarf::get_type`](super::Dwarf::get_type).
+    pub fn type_id(&self) -> Option<TypeId> {
+        self.type_id
+    }
+
+    /// Returns the name of the type of the variable, e.g. `u32` or `&str`.
+    pub fn type_name(&self) -> Option<&str> {
+        self.type_name.as_deref()
+    }
+
+    /// Returns where the value of the variable is stored. `None` if the variable has been
+    /// optimized out entirely.
+    pub fn location(&self) -> Option<&VariableLocation> {
+        self.location.as_ref()
+    }
+
+    /// Returns the path of the source file containing the declaration of the variable.
+    pub fn decl_file(&self) -> Option<&str> {
+        self.decl_file.as_deref()
+    }
+
+    /// Returns the source line on which the variable is declared.
+    pub fn decl_line(&self) -> Option<u64> {
+        self.decl_line
+    }
+}
+
+/// Collects the variables in scope at `addr` within the function entry at `offset`, innermost
+/// scope first. Variables of functions inlined at `addr` are included as well, as they are
+/// nested scopes of the function they are inlined into.
+pub(super) fn scope_variables(
+    dwarf: &gimli::Dwarf<Reader>,
+    units: &[gimli::Unit<Reader>],
+    unit_index: usize,
+    offset: gimli::UnitOffset,
+    addr: usize,
+) -> Result<Vec<Variable>, Box<dyn std::error::Error>> {
+    fn collect(
+        dwarf: &gimli::Dwarf<Reader>,
+        units: &[gimli::Unit<Reader>],
+        unit_index: usize,
+        node: gimli::EntriesTreeNode<Reader>,
+        addr: usize,
+    ) -> Result<Vec<Variable>, Box<dyn std::error::Error>> {
+        let unit = &units[unit_index];
+        let mut variables = Vec::new();
+        let mut inner = Vec::new();
+
+        let mut children = node.children();
+        while let Some(child) = children.next()? {
+            let entry = child.entry();
+            let kind = match entry.tag() {
+                gimli::DW_TAG_formal_parameter => VariableKind::Parameter,
+                gimli::DW_TAG_variable => VariableKind::Local,
+                gimli::DW_TAG_lexical_block | gimli::DW_TAG_inlined_subroutine => {
+                    if entry_ranges(dwarf, unit, entry)?
+                        .iter()
+                        .any(|range| range.contains(&addr))
+                    {
+                        inner = collect(dwarf, units, unit_index, child, addr)?;
+                    }
+                    continue;
+                }
+                _ => continue,
+            };
+            variables.push(variable(dwarf, units, unit_index, entry, kind)?);
+        }
+
+        inner.append(&mut variables);
+        Ok(inner)
+    }
+
+    let mut tree = units[unit_index].entries_tree(Some(offset))?;
+    collect(dwarf, units, unit_index, tree.root()?, addr)
+}
+
+fn variable(
+    dwarf: &gimli::Dwarf<Reader>,
+    units: &[gimli::Unit<Reader>],
+    unit_index: usize,
+    entry: &gimli::DebuggingInformationEntry<Reader>,
+    kind: VariableKind,
+) -> Result<Variable, Box<dyn std::error::Error>> {
+    let unit = &units[unit_index];
+    let offset = entry.offset();
+
+    // Variables of inlined functions refer to the abstract instance for everything but the
+    // location.
+    let name = match find_attr(units, unit_index, offset, gimli::DW_AT_name)? {
+        Some((unit_index, value)) => Some(
+            dwarf
+                .attr_string(&units[unit_index], value)?
+                .to_string_lossy()?
+                .into_owned(),
+        ),
+        None => None,
+    };
+    let type_id = find_attr(units, unit_index, offset, gimli::DW_AT_type)?
+        .and_then(|(unit_index, value)| types::type_id(units, unit_index, value));
+    let type_name = match type_id {
+        Some(type_id) => types::type_name(dwarf, units, type_id)?,
+        None => None,
+    };
+    let decl_file = match find_attr(units, unit_index, offset, gimli::DW_AT_decl_file)? {
+        Some((unit_index, value)) => line::file_attr_path(dwarf, &units[unit_index], value)?,
+        None => None,
+    };
+    let decl_line = find_attr(units, unit_index, offset, gimli::DW_AT_decl_line)?
+        .and_then(|(_, value)| value.udata_value());
+
+    let location = match entry.attr_value(gimli::DW_AT_location)? {
+        Some(value) => attr_location(dwarf, unit, value)?,
+        None => None,
+    };
+
+    Ok(Variable {
+        name,
+        kind,
+        type_id,
+        type_name,
+        location,
+        decl_file,
+        decl_line,
+        unit: unit_index,
+        offset,
+    })
+}
+
+/// Reads a location description attribute, e.g. `DW_AT_location` or `DW_AT_frame_base`.
+pub(super) fn attr_location(
+    dwarf: &gimli::Dwarf<Reader>,
+    unit: &gimli::Unit<Reader>,
+    value: AttributeValue,
+) -> Result<Option<VariableLocation>, Box<dyn std::error::Error>> {
+    if let gimli::AttributeValue::Exprloc(expr) = value {
+        return Ok(Some(VariableLocation::Expression(
+            expr.0.to_slice()?.into_owned(),
+        )));
+    }
+    let mut locations = match dwarf.attr_locations(unit, value)? {
+        Some(locations) => locations,
+        None => return Ok(None),
+    };
+    let mut list = Vec::new();
+    while let Some(location) = locations.next()? {
+        if location.range.begin < location.range.end {
+            list.push((
+                location.range.begin as usize..location.range.end as usize,
+                location.data.0.to_slice()?.into_owned(),
+            ));
+        }
+    }
+    Ok(Some(VariableLocation::List(list)))
+}
+
+/// Finds the value a register had when the function returning to `return_addr` has been called,
+/// as described by a call site parameter within the calling function entry at `offset`.
+/// Returns an expression to be evaluated in the frame of the calling function.
+pub(super) fn call_site_value(
+    units: &[gimli::Unit<Reader>],
+    unit_index: usize,
+    offset: gimli::UnitOffset,
+    return_addr: usize,
+    register: u16,
+) -> Result<Option<Vec<u8>>, Box<dyn std::error::Error>> {
+    fn find_call_site(
+        node: gimli::EntriesTreeNode<Reader>,
+        return_addr: usize,
+    ) -> Result<Option<gimli::UnitOffset>, Box<dyn std::error::Error>> {
+        let mut children = node.children();
+        while let Some(child) = children.next()? {
+            let entry = child.entry();
+            // GNU call sites use the low PC for the return address.
+            let return_pc = match entry.tag() {
+                gimli::DW_TAG_call_site => entry.attr_value(gimli::DW_AT_call_return_pc)?,
+                gimli::DW_TAG_GNU_call_site => entry.attr_value(gimli::DW_AT_low_pc)?,
+                _ => match find_call_site(child, return_addr)? {
+                    Some(offset) => return Ok(Some(offset)),
+                    None => continue,
+                },
+            };
+            if let Some(gimli::AttributeValue::Addr(addr)) = return_pc {
+                if addr as usize == return_addr {
+                    return Ok(Some(entry.offset()));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    let unit = &units[unit_index];
+    let mut tree = unit.entries_tree(Some(offset))?;
+    let call_site = match find_call_site(tree.root()?, return_addr)? {
+        Some(call_site) => call_site,
+        None => return Ok(None),
+    };
+
+    let mut tree = unit.entries_tree(Some(call_site))?;
+    let mut children = tree.root()?.children();
+    while let Some(child) = children.next()? {
+        let entry = child.entry();
+        match entry.tag() {
+            gimli::DW_TAG_call_site_parameter | gimli::DW_TAG_GNU_call_site_parameter => {}
+            _ => continue,
+        }
+        // The location of the parameter is the register it is passed in.
+        let mut operations = match entry.attr_value(gimli::DW_AT_location)? {
+            Some(gimli::AttributeValue::Exprloc(expr)) => expr.operations(unit.encoding()),
+            _ => continue,
+        };
+        match (operations.next()?, operations.next()?) {
+            (Some(gimli::Operation::Register { register: location }), None)
+                if location.0 == register => {}
+            _ => continue,
+        }
+        let value = match entry.attr_value(gimli::DW_AT_call_value)? {
+            Some(value) => Some(value),
+            None => entry.attr_value(gimli::DW_AT_GNU_call_site_value)?,
+        };
+        if let Some(gimli::AttributeValue::Exprloc(expr)) = value {
+            return Ok(Some(expr.0.to_slice()?.into_owned()));
+        }
+    }
+    Ok(None)
+}
@@ -0,0 +1,341 @@
+mod breakpoint;
+mod eval;
+mod event;
+mod lifecycle;
+mod memory_map;
+mod pretty;
+mod readmem;
+mod region_watchpoint;
+mod step;
+mod thread;
+mod unwind;
+mod value;
+mod watchpoint;
+mod writemem;
+
+use nix::{
+    sys::{
+        ptrace,
+        signal::Signal,
+        wait::{waitpid, WaitPidFlag, WaitStatus},
+    },
+    unistd::{getpid, Pid},
+};
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+};
+
+use crate::{
+    symbol::Dwarf,
+    target::unix::{self, DebuggeeStdio, LaunchOptions, UnixTarget},
+};
+
+pub use breakpoint::{Breakpoint, BreakpointError, Breakpoints};
+pub use eval::{Piece, PieceLocation};
+pub use event::DebugEvent;
+pub use lifecycle::{DropPolicy, ExitStatus};
+pub use memory_map::MemoryMap;
+pub use pretty::{FormatContext, PrettyPrinter, ValueFormatter};
+pub use readmem::ReadMemory;
+pub use region_watchpoint::RegionWatchpoint;
+pub use thread::{Thread, ThreadState};
+pub use unwind::Frame;
+pub use value::{Value, ValueKind};
+pub use watchpoint::{Watchpoint, WatchpointError, WatchpointMode};
+pub use writemem::WriteMemory;
+
+/// This structure holds the state of a debuggee on Linux based systems
+/// You can use it to read & write debuggee's memory, pause it, set breakpoints, etc.
+pub struct LinuxTarget {
+    pid: Pid,
+    breakpoints: Breakpoints,
+    // Hardware watchpoints, which are set on all threads.
+    watchpoints: Vec<Watchpoint>,
+    // Watchpoints implemented by the protection of pages, which apply to all threads.
+    region_watchpoints: Vec<RegionWatchpoint>,
+    // Threads of the debuggee which are traced by us.
+    threads: Vec<Pid>,
+    // Forked children of the debuggee which have been detached before their fork event.
+    forked_children: Vec<Pid>,
+    // The thread which has reported the last debug event.
+    event_thread: Pid,
+    // A signal that will be delivered to `event_thread` when it is resumed.
+    pending_signal: Option<Signal>,
+    // Statuses of threads which have stopped while we were stopping them on our own. They are
+    // reported by `next_event` before waiting for new ones.
+    deferred_statuses: Vec<WaitStatus>,
+    // Threads which have been sent a `SIGSTOP` by us that hasn't arrived yet.
+    stopping_threads: Vec<Pid>,
+    // Our ends of the pipes and the PTY connected to the debuggee.
+    stdio: DebuggeeStdio,
+    // What happens to the debuggee when this target is dropped.
+    drop_policy: DropPolicy,
+    // How the debuggee has terminated, once it has been reported.
+    exit_status: Option<ExitStatus>,
+}
+
+impl UnixTarget for LinuxTarget {
+    /// Provides the Pid of the debugee process
+    fn pid(&self) -> Pid {
+        self.pid
+    }
+
+    /// Continues execution of the thread which has reported the last debug event.
+    /// If it is stopped at a breakpoint, the original instruction is executed first.
+    /// If it has been stopped by a signal, the signal is delivered.
+    fn unpause(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.step_over_breakpoint(self.event_thread)?;
+        // A status reported while stepping over the breakpoint leaves the thread stopped until it
+        // is reported by `next_event`.
+        if !self.has_deferred_status(self.event_thread) {
+            ptrace::cont(self.event_thread, self.pending_signal)?;
+        }
+        Ok(())
+    }
+}
+
+impl LinuxTarget {
+    fn new(pid: Pid, drop_policy: DropPolicy) -> LinuxTarget {
+        LinuxTarget {
+            pid,
+            breakpoints: Breakpoints::new(pid),
+            watchpoints: Vec::new(),
+            region_watchpoints: Vec::new(),
+            threads: vec![pid],
+            forked_children: Vec::new(),
+            event_thread: pid,
+            pending_signal: None,
+            deferred_statuses: Vec::new(),
+            stopping_threads: Vec::new(),
+            stdio: DebuggeeStdio::default(),
+            drop_policy,
+            exit_status: None,
+        }
+    }
+
+    /// Launches a new debuggee process, either from a path or from `LaunchOptions`.
+    /// The debuggee is killed when the target is dropped, see [`LinuxTarget::set_drop_policy`].
+    pub fn launch(
+        options: impl Into<LaunchOptions>,
+    ) -> Result<LinuxTarget, Box<dyn std::error::Error>> {
+        let (pid, stdio) = unix::launch(&options.into())?;
+        let mut target = LinuxTarget::new(pid, DropPolicy::Kill);
+        target.stdio = stdio;
+        target.set_ptrace_options(pid)?;
+        Ok(target)
+    }
+
+    /// Attaches process as a debugee.
+    /// The main thread is stopped, while the other threads are traced and keep running.
+    /// The debuggee is detached when the target is dropped, see [`LinuxTarget::set_drop_policy`].
+    pub fn attach(pid: Pid) -> Result<LinuxTarget, Box<dyn std::error::Error>> {
+        unix::attach(pid)?;
+        let mut target = LinuxTarget::new(pid, DropPolicy::Detach);
+        target.set_ptrace_options(pid)?;
+        target.attach_threads()?;
+        Ok(target)
+    }
+
+    /// Uses this process as a debuggee.
+    pub fn me() -> LinuxTarget {
+        LinuxTarget::new(getpid(), DropPolicy::Leave)
+    }
+
+    /// Provides our ends of the standard streams of a launched debuggee which are connected to
+    /// pipes or a PTY. They can be taken out, e.g. to read the output in another thread.
+    pub fn stdio(&mut self) -> &mut DebuggeeStdio {
+        &mut self.stdio
+    }
+
+    /// Provides a view into the set of software breakpoints of a debuggee.
+    /// It can be used to set, disable, and remove breakpoints.
+    pub fn breakpoints(&mut self) -> &mut Breakpoints {
+        &mut self.breakpoints
+    }
+
+    /// Executes the original instruction of the breakpoint `thread` is stopped at, if any.
+    /// This has to be done before resuming a thread, as otherwise it would hit the same
+    /// breakpoint again.
+    /// Returns `true` if an instruction has been executed. If the thread reports another status
+    /// instead, e.g. a signal, the status is reported by `next_event` later on.
+    fn step_over_breakpoint(&mut self, thread: Pid) -> Result<bool, Box<dyn std::error::Error>> {
+        let addr = ptrace::getregs(thread)?.rip as usize;
+        let breakpoint = match self.breakpoints.find_by_addr(addr) {
+            Some(breakpoint) if breakpoint.is_enabled() => breakpoint,
+            _ => return Ok(false),
+        };
+
+        self.write()
+            .write(&breakpoint.original_byte(), addr)
+            .apply()?;
+        ptrace::step(thread, None)?;
+        let status = waitpid(thread, Some(WaitPidFlag::__WALL))?;
+        let restored = self.write().write(&breakpoint::INT3, addr).apply();
+        match status {
+            WaitStatus::Stopped(_, Signal::SIGTRAP) => {
+                restored?;
+                Ok(true)
+            }
+            // The breakpoint can't be restored once the debuggee has exited.
+            WaitStatus::Exited(..) | WaitStatus::Signaled(..) => {
+                self.deferred_statuses.push(status);
+                Ok(false)
+            }
+            status => {
+                restored?;
+                self.deferred_statuses.push(status);
+                Ok(false)
+            }
+        }
+    }
+
+    /// Returns the mappings in the virtual address space of the debuggee, sorted by address.
+    pub fn memory_maps(&self) -> Result<Vec<MemoryMap>, Box<dyn std::error::Error>> {
+        memory_map::memory_maps(self.pid)
+    }
+
+    /// Returns the difference between the addresses the module described by `debuginfo` is
+    /// loaded at and the addresses in its debug info. It is non-zero for position-independent
+    /// executables and shared libraries, so addresses from the debug info, e.g. of symbols or
+    /// source lines, have to be relocated by it before setting breakpoints.
+    pub fn load_bias(&self, debuginfo: &Dwarf) -> Result<usize, Box<dyn std::error::Error>> {
+        memory_map::load_bias(&self.memory_maps()?, debuginfo)
+    }
+
+    /// Reads memory from a debuggee process.
+    pub fn read(&self) -> ReadMemory<'_> {
+        ReadMemory::new(self.pid())
+    }
+
+    /// Writes memory to a debuggee process.
+    /// Read-only pages (e.g. code) are written as well, so this can be used to patch instructions.
+    pub fn write(&self) -> WriteMemory<'_> {
+        WriteMemory::new(self.pid())
+    }
+
+    /// Reads the register values from the main thread of a debuggee process.
+    pub fn read_regs(&self) -> Result<libc::user_regs_struct, Box<dyn std::error::Error>> {
+        nix::sys::ptrace::getregs(self.pid()).map_err(|err| err.into())
+    }
+
+    /// Writes the register values for the main thread of a debuggee process.
+    pub fn write_regs(
+        &self,
+        regs: libc::user_regs_struct,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        nix::sys::ptrace::setregs(self.pid(), regs).map_err(|err| err.into())
+    }
+
+    /// Reads the register values of a thread of a debuggee process.
+    /// The thread has to be stopped, e.g. because it has reported the last debug event.
+    pub fn read_thread_regs(
+        &self,
+        thread: Pid,
+    ) -> Result<libc::user_regs_struct, Box<dyn std::error::Error>> {
+        ptrace::getregs(thread).map_err(|err| err.into())
+    }
+
+    /// Writes the register values of a thread of a debuggee process.
+    /// The thread has to be stopped, e.g. because it has reported the last debug event.
+    pub fn write_thread_regs(
+        &self,
+        thread: Pid,
+        regs: libc::user_regs_struct,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        ptrace::setregs(thread, regs).map_err(|err| err.into())
+    }
+
+    /// Let the debuggee process execute the specified syscall.
+    #[allow(clippy::too_many_arguments)]
+    pub fn syscall(
+        &self,
+        num: libc::c_ulonglong,
+        arg1: libc::c_ulonglong,
+        arg2: libc::c_ulonglong,
+        arg3: libc::c_ulonglong,
+        arg4: libc::c_ulonglong,
+        arg5: libc::c_ulonglong,
+        arg6: libc::c_ulonglong,
+    ) -> Result<libc::c_ulonglong, Box<dyn std::error::Error>> {
+        self.thread_syscall(self.pid(), num, [arg1, arg2, arg3, arg4, arg5, arg6])
+    }
+
+    /// Let a stopped thread of the debuggee execute the specified syscall.
+    pub(super) fn thread_syscall(
+        &self,
+        thread: Pid,
+        num: libc::c_ulonglong,
+        args: [libc::c_ulonglong; 6],
+    ) -> Result<libc::c_ulonglong, Box<dyn std::error::Error>> {
+        // Write arguments
+        let orig_regs = self.read_thread_regs(thread)?;
+        let mut new_regs = orig_regs;
+        new_regs.rax = num;
+        new_regs.rdi = args[0];
+        new_regs.rsi = args[1];
+        new_regs.rdx = args[2];
+        new_regs.r10 = args[3];
+        new_regs.r8 = args[4];
+        new_regs.r9 = args[5];
+        self.write_thread_regs(thread, new_regs)?;
+
+        // Write syscall instruction
+        // FIXME search for an existing syscall instruction once instead
+        let old_inst = nix::sys::ptrace::read(thread, new_regs.rip as *mut _)?;
+        nix::sys::ptrace::write(
+            thread,
+            new_regs.rip as *mut _,
+            0x050f/*x86_64 syscall*/ as *mut _,
+        )?;
+
+        // Perform syscall
+        nix::sys::ptrace::step(thread, None)?;
+        nix::sys::wait::waitpid(thread, Some(WaitPidFlag::__WALL))?;
+
+        // Read return value
+        let res = self.read_thread_regs(thread)?.rax;
+
+        // Restore old code and registers
+        nix::sys::ptrace::write(thread, new_regs.rip as *mut _, old_inst as *mut _)?;
+        self.write_thread_regs(thread, orig_regs)?;
+
+        Ok(res)
+    }
+
+    /// Let the debuggee process map memory.
+    pub fn mmap(
+        &self,
+        addr: *mut libc::c_void,
+        length: libc::size_t,
+        prot: libc::c_int,
+        flags: libc::c_int,
+        fd: libc::c_int,
+        offset: libc::off_t,
+    ) -> Result<libc::c_ulonglong, Box<dyn std::error::Error>> {
+        self.syscall(
+            libc::SYS_mmap as _,
+            addr as _,
+            length as _,
+            prot as _,
+            flags as _,
+            fd as _,
+            offset as _,
+        )
+    }
+}
+
+/// Returns the start of a process's virtual memory address range.
+/// This can be useful for calculation of relative addresses in memory.
+pub fn get_addr_range(pid: Pid) -> Result<usize, Box<dyn std::error::Error>> {
+    let file = File::open(format!("/proc/{}/maps", pid))?;
+    let mut bufread = BufReader::new(file);
+    let mut proc_map = String::new();
+
+    bufread.read_line(&mut proc_map)?;
+
+    let proc_data: Vec<_> = proc_map.split(' ').collect();
+    let addr_range: Vec<_> = proc_data[0].split('-').collect();
+
+    Ok(usize::from_str_radix(addr_range[0], 16)?)
+}
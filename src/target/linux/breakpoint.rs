@@ -0,0 +1,219 @@
+use nix::unistd::Pid;
+use std::fmt;
+
+use super::{ReadMemory, WriteMemory};
+
+/// The x86_64 `int3` instruction, which raises a `SIGTRAP` when executed.
+pub(super) const INT3: u8 = 0xcc;
+
+/// A software breakpoint set in the debuggee's code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Breakpoint {
+    addr: usize,
+    // The code byte replaced by the `int3` instruction.
+    original_byte: u8,
+    enabled: bool,
+}
+
+impl Breakpoint {
+    /// Returns the address of the breakpoint.
+    pub fn addr(&self) -> usize {
+        self.addr
+    }
+
+    /// Returns the byte at the breakpoint address before the breakpoint has been inserted.
+    pub fn original_byte(&self) -> u8 {
+        self.original_byte
+    }
+
+    /// Returns `true` if the breakpoint is currently inserted in the debuggee's code.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+}
+
+/// Describes why a breakpoint operation has failed for a given address.
+#[derive(Debug)]
+pub enum BreakpointError {
+    /// A breakpoint is already set at this address.
+    AlreadySet(usize),
+    /// There is no breakpoint at this address.
+    NotFound(usize),
+    /// The debuggee's code couldn't be read or patched at this address.
+    Memory {
+        addr: usize,
+        error: Box<dyn std::error::Error>,
+    },
+}
+
+impl fmt::Display for BreakpointError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BreakpointError::AlreadySet(addr) => {
+                write!(f, "Breakpoint at 0x{:x} is already set", addr)
+            }
+            BreakpointError::NotFound(addr) => write!(f, "No breakpoint at 0x{:x}", addr),
+            BreakpointError::Memory { addr, error } => {
+                write!(f, "Failed to patch breakpoint at 0x{:x}: {}", addr, error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BreakpointError {}
+
+/// A set of software breakpoints of a debuggee.
+/// Breakpoints are implemented by replacing the first byte of an instruction with `int3`.
+pub struct Breakpoints {
+    pid: Pid,
+    // Sorted by address.
+    breakpoints: Vec<Breakpoint>,
+}
+
+impl Breakpoints {
+    pub(super) fn new(pid: Pid) -> Self {
+        Breakpoints {
+            pid,
+            breakpoints: Vec::new(),
+        }
+    }
+
+    /// Sets and enables breakpoints at the provided addresses.
+    /// In the case of error, returns a list of breakpoints that weren't set along with the error
+    /// descriptions. All other breakpoints are set regardless.
+    pub fn set(&mut self, addrs: &[usize]) -> Result<(), Vec<BreakpointError>> {
+        let mut errors = Vec::new();
+        for &addr in addrs {
+            let index = match self.index_of(addr) {
+                Ok(_) => {
+                    errors.push(BreakpointError::AlreadySet(addr));
+                    continue;
+                }
+                Err(index) => index,
+            };
+
+            let mut original_byte = 0u8;
+            let res = unsafe {
+                ReadMemory::new(self.pid)
+                    .read(&mut original_byte, addr)
+                    .apply_all()
+            };
+            if let Err(error) = res {
+                errors.push(BreakpointError::Memory {
+                    addr,
+                    error: Box::new(error),
+                });
+                continue;
+            }
+
+            let mut breakpoint = Breakpoint {
+                addr,
+                original_byte,
+                enabled: false,
+            };
+            if let Err(error) = patch(self.pid, &mut breakpoint, true) {
+                errors.push(error);
+                continue;
+            }
+            self.breakpoints.insert(index, breakpoint);
+        }
+        result(errors)
+    }
+
+    /// Returns a list of all set breakpoints, sorted by address.
+    pub fn get_all(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    /// Forgets all breakpoints without restoring the original code.
+    /// Used when the debuggee's code has been replaced, e.g. by `execve`.
+    pub(super) fn clear(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    /// Returns the breakpoint set at the given address, if any.
+    pub fn find_by_addr(&self, addr: usize) -> Option<&Breakpoint> {
+        self.index_of(addr)
+            .ok()
+            .map(|index| &self.breakpoints[index])
+    }
+
+    /// Re-enables previously disabled breakpoints at the given addresses.
+    pub fn enable(&mut self, addrs: &[usize]) -> Result<(), Vec<BreakpointError>> {
+        self.update(addrs, true)
+    }
+
+    /// Temporarily disables breakpoints at the given addresses, restoring the original code.
+    pub fn disable(&mut self, addrs: &[usize]) -> Result<(), Vec<BreakpointError>> {
+        self.update(addrs, false)
+    }
+
+    /// Removes breakpoints at the given addresses, restoring the original code.
+    pub fn remove(&mut self, addrs: &[usize]) -> Result<(), Vec<BreakpointError>> {
+        let mut errors = Vec::new();
+        for &addr in addrs {
+            let index = match self.index_of(addr) {
+                Ok(index) => index,
+                Err(_) => {
+                    errors.push(BreakpointError::NotFound(addr));
+                    continue;
+                }
+            };
+            if let Err(error) = patch(self.pid, &mut self.breakpoints[index], false) {
+                errors.push(error);
+                continue;
+            }
+            self.breakpoints.remove(index);
+        }
+        result(errors)
+    }
+
+    fn index_of(&self, addr: usize) -> Result<usize, usize> {
+        self.breakpoints
+            .binary_search_by_key(&addr, |breakpoint| breakpoint.addr)
+    }
+
+    /// Enables or disables breakpoints at the given addresses.
+    fn update(&mut self, addrs: &[usize], enable: bool) -> Result<(), Vec<BreakpointError>> {
+        let mut errors = Vec::new();
+        for &addr in addrs {
+            let res = match self.index_of(addr) {
+                Ok(index) => patch(self.pid, &mut self.breakpoints[index], enable),
+                Err(_) => Err(BreakpointError::NotFound(addr)),
+            };
+            if let Err(error) = res {
+                errors.push(error);
+            }
+        }
+        result(errors)
+    }
+}
+
+/// Writes either `int3` or the original byte to the breakpoint address.
+fn patch(pid: Pid, breakpoint: &mut Breakpoint, enable: bool) -> Result<(), BreakpointError> {
+    if breakpoint.enabled == enable {
+        return Ok(());
+    }
+    let byte = if enable {
+        INT3
+    } else {
+        breakpoint.original_byte
+    };
+    WriteMemory::new(pid)
+        .write(&byte, breakpoint.addr)
+        .apply()
+        .map_err(|error| BreakpointError::Memory {
+            addr: breakpoint.addr,
+            error,
+        })?;
+    breakpoint.enabled = enable;
+    Ok(())
+}
+
+fn result(errors: Vec<BreakpointError>) -> Result<(), Vec<BreakpointError>> {
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
@@ -0,0 +1,366 @@
+use nix::sys::ptrace;
+
+use super::{Frame, LinuxTarget};
+use crate::symbol::{Dwarf, Variable};
+
+/// The maximum nesting of expressions evaluated on behalf of other expressions, e.g. entry
+/// values which are evaluated in the frame of the caller.
+const MAX_EVAL_DEPTH: usize = 8;
+
+type Expression<'a> = gimli::Expression<gimli::EndianSlice<'a, gimli::LittleEndian>>;
+
+/// Where a piece of the value of a variable is stored, see `LinuxTarget::eval_variable`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PieceLocation {
+    /// The value is stored in memory at the given address.
+    Memory(usize),
+    /// The value is stored in a register, identified by its DWARF register number.
+    Register(u16),
+    /// The value isn't stored anywhere, but is known from the debug info. The bytes are in
+    /// little-endian order.
+    Value(Vec<u8>),
+    /// The value has been optimized out.
+    OptimizedOut,
+}
+
+/// A piece of the value of a variable.
+/// The value of a variable can be split into several pieces, e.g. if the fields of a struct are
+/// stored in separate registers. A variable stored in a single place has a single piece without
+/// a size.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Piece {
+    /// The size of the piece, or `None` if it is the whole value.
+    pub size_in_bits: Option<u64>,
+    /// The offset of the piece within its location, e.g. the register.
+    pub bit_offset: Option<u64>,
+    pub location: PieceLocation,
+}
+
+impl LinuxTarget {
+    /// Evaluates the location of a variable in the frame `frames[index]` of the thread which has
+    /// reported the last debug event. `frames` have to be the result of `unwind`, as
+    /// some values are recovered from the frames of the callers.
+    ///
+    /// Addresses of static variables are relocated to where the module described by `debuginfo`
+    /// is loaded, see `load_bias`. Thread-local variables are only supported for the executable,
+    /// not for shared libraries.
+    pub fn eval_variable(
+        &self,
+        debuginfo: &Dwarf,
+        frames: &[Frame],
+        index: usize,
+        variable: &Variable,
+    ) -> Result<Vec<Piece>, Box<dyn std::error::Error>> {
+        let addr = frames[index].lookup_addr(index == 0);
+        match variable
+            .location()
+            .and_then(|location| location.expression_at(addr))
+        {
+            Some(expr) => self.eval_expression(debuginfo, frames, index, expr, variable.unit, 0),
+            None => Ok(vec![Piece {
+                size_in_bits: None,
+                bit_offset: None,
+                location: PieceLocation::OptimizedOut,
+            }]),
+        }
+    }
+
+    /// Reads `size` bytes of the value described by `pieces` (see `eval_variable`), with
+    /// register values taken from `frame`.
+    pub fn read_pieces(
+        &self,
+        frame: &Frame,
+        pieces: &[Piece],
+        size: usize,
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let mut bytes = Vec::with_capacity(size);
+        for piece in pieces {
+            let len = match piece.size_in_bits {
+                Some(bits) if bits % 8 == 0 => bits as usize / 8,
+                Some(_) => return Err("Pieces which aren't whole bytes are not supported".into()),
+                None => size.saturating_sub(bytes.len()),
+            };
+            let offset = match piece.bit_offset {
+                Some(bits) if bits % 8 == 0 => bits as usize / 8,
+                Some(_) => return Err("Pieces which aren't whole bytes are not supported".into()),
+                None => 0,
+            };
+
+            match &piece.location {
+                PieceLocation::Memory(addr) => {
+                    let mut buf = vec![0; len];
+                    self.read()
+                        .read_slice(&mut buf, addr + offset)
+                        .apply_all()?;
+                    bytes.extend(buf);
+                }
+                PieceLocation::Register(register) => {
+                    let value = register_value(frame, *register)?.to_le_bytes();
+                    if offset + len > value.len() {
+                        return Err(format!(
+                            "Piece of {} bytes doesn't fit into register {}",
+                            len, register
+                        )
+                        .into());
+                    }
+                    bytes.extend(&value[offset..offset + len]);
+                }
+                PieceLocation::Value(value) => {
+                    // Missing bytes of values are implicitly zero.
+                    let value = value.iter().skip(offset).chain(std::iter::repeat(&0));
+                    bytes.extend(value.take(len));
+                }
+                PieceLocation::OptimizedOut => {
+                    return Err("The value has been optimized out".into());
+                }
+            }
+        }
+
+        if bytes.len() < size {
+            return Err(format!(
+                "Only {} of {} bytes of the value are described by the debug info",
+                bytes.len(),
+                size
+            )
+            .into());
+        }
+        bytes.truncate(size);
+        Ok(bytes)
+    }
+
+    fn eval_expression(
+        &self,
+        debuginfo: &Dwarf,
+        frames: &[Frame],
+        index: usize,
+        expr: &[u8],
+        unit: usize,
+        depth: usize,
+    ) -> Result<Vec<Piece>, Box<dyn std::error::Error>> {
+        if depth > MAX_EVAL_DEPTH {
+            return Err("DWARF expressions are nested too deeply".into());
+        }
+        let frame = &frames[index];
+        let expr = gimli::Expression(gimli::EndianSlice::new(expr, gimli::LittleEndian));
+        let mut eval = expr.evaluation(debuginfo.get_unit_encoding(unit));
+
+        let mut result = eval.evaluate()?;
+        loop {
+            result = match result {
+                gimli::EvaluationResult::Complete => break,
+                gimli::EvaluationResult::RequiresMemory {
+                    address,
+                    size,
+                    base_type,
+                    ..
+                } => {
+                    check_generic(base_type)?;
+                    let mut value = [0u8; 8];
+                    let len = usize::from(size).min(value.len());
+                    self.read()
+                        .read_slice(&mut value[..len], address as usize)
+                        .apply_all()?;
+                    eval.resume_with_memory(gimli::Value::Generic(u64::from_le_bytes(value)))?
+                }
+                gimli::EvaluationResult::RequiresRegister {
+                    register,
+                    base_type,
+                } => {
+                    check_generic(base_type)?;
+                    let value = register_value(frame, register.0)?;
+                    eval.resume_with_register(gimli::Value::Generic(value))?
+                }
+                gimli::EvaluationResult::RequiresFrameBase => {
+                    let frame_base = self.frame_base(debuginfo, frames, index, depth)?;
+                    eval.resume_with_frame_base(frame_base)?
+                }
+                gimli::EvaluationResult::RequiresCallFrameCfa => {
+                    let cfa = frame.cfa.ok_or("The CFA of the frame is unknown")?;
+                    eval.resume_with_call_frame_cfa(cfa as u64)?
+                }
+                gimli::EvaluationResult::RequiresTls(offset) => {
+                    eval.resume_with_tls(self.tls_address(debuginfo, offset)?)?
+                }
+                gimli::EvaluationResult::RequiresEntryValue(expr) => {
+                    let value = self.entry_value(debuginfo, frames, index, expr, unit, depth)?;
+                    eval.resume_with_entry_value(gimli::Value::Generic(value))?
+                }
+                gimli::EvaluationResult::RequiresRelocatedAddress(address) => {
+                    let bias = frame.load_bias as u64;
+                    eval.resume_with_relocated_address(address.wrapping_add(bias))?
+                }
+                gimli::EvaluationResult::RequiresIndexedAddress { index, .. } => {
+                    let address = debuginfo.get_indexed_address(unit, index)?;
+                    eval.resume_with_indexed_address(address as u64)?
+                }
+                result => {
+                    return Err(
+                        format!("Unsupported DWARF expression requirement: {:?}", result).into(),
+                    )
+                }
+            };
+        }
+
+        eval.result()
+            .into_iter()
+            .map(|piece| {
+                let location = match piece.location {
+                    gimli::Location::Empty => PieceLocation::OptimizedOut,
+                    gimli::Location::Register { register } => PieceLocation::Register(register.0),
+                    gimli::Location::Address { address } => PieceLocation::Memory(address as usize),
+                    gimli::Location::Value { value } => PieceLocation::Value(value_bytes(value)),
+                    gimli::Location::Bytes { value } => PieceLocation::Value(value.to_vec()),
+                    gimli::Location::ImplicitPointer { .. } => {
+                        return Err("Implicit pointers are not supported".into())
+                    }
+                };
+                Ok(Piece {
+                    size_in_bits: piece.size_in_bits,
+                    bit_offset: piece.bit_offset,
+                    location,
+                })
+            })
+            .collect()
+    }
+
+    /// Computes the frame base of the function executing in `frames[index]`, which is the base
+    /// address of variables with a `DW_OP_fbreg` location.
+    fn frame_base(
+        &self,
+        debuginfo: &Dwarf,
+        frames: &[Frame],
+        index: usize,
+        depth: usize,
+    ) -> Result<u64, Box<dyn std::error::Error>> {
+        let addr = frames[index].lookup_addr(index == 0);
+        let (location, unit) = debuginfo
+            .get_frame_base(addr)?
+            .ok_or("The function has no frame base")?;
+        let expr = location
+            .expression_at(addr)
+            .ok_or("The frame base is unknown at this address")?;
+        let pieces = self.eval_expression(debuginfo, frames, index, expr, unit, depth + 1)?;
+        pieces_value(&frames[index], &pieces)
+    }
+
+    /// Computes the value an expression had when the function executing in `frames[index]` has
+    /// been called, using the call site parameters of its caller.
+    fn entry_value(
+        &self,
+        debuginfo: &Dwarf,
+        frames: &[Frame],
+        index: usize,
+        expr: Expression,
+        unit: usize,
+        depth: usize,
+    ) -> Result<u64, Box<dyn std::error::Error>> {
+        // Only the values of registers used to pass parameters can be recovered.
+        let mut operations = expr.operations(debuginfo.get_unit_encoding(unit));
+        let register = match (operations.next()?, operations.next()?) {
+            (Some(gimli::Operation::Register { register }), None) => register.0,
+            _ => return Err("Unsupported entry value expression".into()),
+        };
+
+        let caller = frames
+            .get(index + 1)
+            .ok_or("The caller of the frame is unknown")?;
+        let value = debuginfo
+            .get_call_site_value(caller.pc.wrapping_sub(caller.load_bias), register)?
+            .ok_or_else(|| format!("The entry value of register {} is unknown", register))?;
+        let pieces = self.eval_expression(
+            debuginfo,
+            frames,
+            index + 1,
+            &value.expr,
+            value.unit,
+            depth + 1,
+        )?;
+        pieces_value(caller, &pieces)
+    }
+
+    /// Computes the address of a thread-local variable of the executable for the thread which
+    /// has reported the last debug event.
+    fn tls_address(
+        &self,
+        debuginfo: &Dwarf,
+        offset: u64,
+    ) -> Result<u64, Box<dyn std::error::Error>> {
+        // The thread-local storage blocks of shared libraries can only be found through the
+        // dynamic linker.
+        let executable = std::fs::read_link(format!("/proc/{}/exe", self.pid))?;
+        if executable != debuginfo.path() {
+            return Err(format!(
+                "Thread-local variables of {} are not supported, only the ones of the executable",
+                debuginfo.path().display()
+            )
+            .into());
+        }
+        let (size, align) = debuginfo
+            .get_tls_block()
+            .ok_or("The executable has no thread-local storage")?;
+        // The thread-local storage block of the executable is placed right below the thread
+        // pointer on x86_64.
+        let thread_pointer = ptrace::getregs(self.event_thread)?.fs_base;
+        let block_size = size.next_multiple_of(align);
+        Ok(thread_pointer - block_size as u64 + offset)
+    }
+}
+
+/// Returns the value of a register in a frame.
+fn register_value(frame: &Frame, register: u16) -> Result<u64, Box<dyn std::error::Error>> {
+    frame
+        .regs
+        .get(register as usize)
+        .copied()
+        .flatten()
+        .ok_or_else(|| format!("The value of register {} is unknown", register).into())
+}
+
+/// Returns the value computed by an expression which doesn't describe the location of a variable,
+/// e.g. a frame base.
+fn pieces_value(frame: &Frame, pieces: &[Piece]) -> Result<u64, Box<dyn std::error::Error>> {
+    match pieces {
+        [Piece {
+            size_in_bits: None,
+            location,
+            ..
+        }] => match location {
+            // A plain value on the stack of the expression is represented as an address.
+            PieceLocation::Memory(addr) => Ok(*addr as u64),
+            PieceLocation::Register(register) => register_value(frame, *register),
+            PieceLocation::Value(value) => {
+                let mut bytes = [0u8; 8];
+                let len = value.len().min(bytes.len());
+                bytes[..len].copy_from_slice(&value[..len]);
+                Ok(u64::from_le_bytes(bytes))
+            }
+            PieceLocation::OptimizedOut => Err("The value has been optimized out".into()),
+        },
+        _ => Err(format!("Unexpected result of a DWARF expression: {:?}", pieces).into()),
+    }
+}
+
+/// Returns the little-endian bytes of a value computed by a DWARF expression.
+fn value_bytes(value: gimli::Value) -> Vec<u8> {
+    match value {
+        gimli::Value::Generic(value) | gimli::Value::U64(value) => value.to_le_bytes().to_vec(),
+        gimli::Value::I8(value) => value.to_le_bytes().to_vec(),
+        gimli::Value::U8(value) => value.to_le_bytes().to_vec(),
+        gimli::Value::I16(value) => value.to_le_bytes().to_vec(),
+        gimli::Value::U16(value) => value.to_le_bytes().to_vec(),
+        gimli::Value::I32(value) => value.to_le_bytes().to_vec(),
+        gimli::Value::U32(value) => value.to_le_bytes().to_vec(),
+        gimli::Value::I64(value) => value.to_le_bytes().to_vec(),
+        gimli::Value::F32(value) => value.to_le_bytes().to_vec(),
+        gimli::Value::F64(value) => value.to_le_bytes().to_vec(),
+    }
+}
+
+/// Typed DWARF operations refer to base types, which are not supported.
+fn check_generic(base_type: gimli::UnitOffset) -> Result<(), Box<dyn std::error::Error>> {
+    if base_type.0 == 0 {
+        Ok(())
+    } else {
+        Err("Typed DWARF operations are not supported".into())
+    }
+}
@@ -0,0 +1,303 @@
+use nix::{
+    sys::{
+        ptrace,
+        signal::Signal,
+        wait::{waitpid, WaitPidFlag, WaitStatus},
+    },
+    unistd::Pid,
+};
+
+use std::{path::Path, time::Duration};
+
+use super::{watchpoint, ExitStatus, LinuxTarget};
+
+/// How often the threads of the debuggee are polled while a child which isn't part of the
+/// debuggee has a status that hasn't been waited for yet.
+const FOREIGN_STATUS_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// An event reported by a debuggee, see [`LinuxTarget::next_event`].
+/// Except for `Exited` and `Killed`, the thread that has reported the event is stopped until
+/// the debuggee is resumed with `unpause`. Other threads keep running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugEvent {
+    /// A software breakpoint has been hit.
+    /// The instruction pointer of `thread` is set to the breakpoint address.
+    BreakpointHit { addr: usize, thread: Pid },
+    /// A hardware watchpoint set at `addr` has been hit, along with the watched value before and
+    /// after the access. Both values are the same unless the memory has been written.
+    /// Execution watchpoints are reported before the instruction is executed.
+    WatchpointHit {
+        addr: usize,
+        thread: Pid,
+        old_value: u64,
+        new_value: u64,
+    },
+    /// A stepping request (e.g. [`LinuxTarget::step`]) has been completed.
+    SingleStep { thread: Pid },
+    /// The memory watched by a region watchpoint starting at `region` has been accessed at
+    /// `addr`. The access has already been executed.
+    RegionAccess {
+        region: usize,
+        addr: usize,
+        thread: Pid,
+    },
+    /// A signal is about to be delivered to a thread.
+    /// The signal is delivered when the debuggee is resumed.
+    Signal { signal: Signal, thread: Pid },
+    /// The debuggee has exited with the given exit code, see [`LinuxTarget::exit_status`].
+    Exited(i32),
+    /// The debuggee has been terminated by the given signal, see [`LinuxTarget::exit_status`].
+    Killed(Signal),
+    /// A new thread has been created by the debuggee.
+    /// The creating thread is stopped, while the new thread is already running.
+    ThreadCreated(Pid),
+    /// The debuggee has executed a new program with `execve`.
+    /// All breakpoints and watchpoints have been removed, as the code and the memory they were set
+    /// in don't exist anymore.
+    Exec,
+    /// The debuggee has forked a child process with the given pid.
+    /// The child process is not debugged.
+    Fork(Pid),
+}
+
+impl LinuxTarget {
+    /// Configures which events are reported by a thread of the debuggee.
+    /// Threads created by a traced thread inherit its options.
+    pub(super) fn set_ptrace_options(&self, thread: Pid) -> Result<(), Box<dyn std::error::Error>> {
+        ptrace::setoptions(
+            thread,
+            ptrace::Options::PTRACE_O_TRACECLONE
+                | ptrace::Options::PTRACE_O_TRACEFORK
+                | ptrace::Options::PTRACE_O_TRACEVFORK
+                | ptrace::Options::PTRACE_O_TRACEEXEC,
+        )?;
+        Ok(())
+    }
+
+    /// Blocks & waits for a next debug event to occur.
+    /// The debuggee has to be resumed with `unpause` (or one of the stepping functions) first.
+    /// Like all ptrace requests, this has to be called from the thread which has launched or
+    /// attached the debuggee.
+    pub fn next_event(&mut self) -> Result<DebugEvent, Box<dyn std::error::Error>> {
+        loop {
+            let status = if self.deferred_statuses.is_empty() {
+                self.wait_any()?
+            } else {
+                self.deferred_statuses.remove(0)
+            };
+            self.pending_signal = None;
+
+            let (thread, event) = match status {
+                WaitStatus::Exited(pid, code) if pid == self.pid => {
+                    self.exit_status = Some(ExitStatus::Exited(code));
+                    (pid, DebugEvent::Exited(code))
+                }
+                WaitStatus::Signaled(pid, signal, _) if pid == self.pid => {
+                    self.exit_status = Some(ExitStatus::Killed(signal));
+                    (pid, DebugEvent::Killed(signal))
+                }
+                WaitStatus::Exited(thread, _) | WaitStatus::Signaled(thread, _, _) => {
+                    // A thread other than the main one has exited.
+                    self.threads.retain(|&tid| tid != thread);
+                    continue;
+                }
+                WaitStatus::Stopped(tid, Signal::SIGSTOP)
+                    if self.stopping_threads.contains(&tid) =>
+                {
+                    // The thread has been stopped by us, but has reported another status first.
+                    self.stopping_threads.retain(|&thread| thread != tid);
+                    ptrace::cont(tid, None)?;
+                    continue;
+                }
+                WaitStatus::Stopped(tid, Signal::SIGSTOP) if !self.threads.contains(&tid) => {
+                    // New threads and forked children start with `SIGSTOP`, which can be reported
+                    // before the `PTRACE_EVENT_*` event of their parent.
+                    if self.is_own_thread(tid) {
+                        if !self.watchpoints.is_empty() {
+                            self.set_debug_registers(tid)?;
+                        }
+                        self.threads.push(tid);
+                        ptrace::cont(tid, None)?;
+                    } else {
+                        self.forked_children.push(tid);
+                        ptrace::detach(tid, None)?;
+                    }
+                    continue;
+                }
+                WaitStatus::Stopped(thread, Signal::SIGTRAP) => (thread, self.trap_event(thread)?),
+                WaitStatus::Stopped(thread, Signal::SIGSEGV) => {
+                    match self.region_watchpoint_event(thread)? {
+                        Some(event) => (thread, event),
+                        None => continue,
+                    }
+                }
+                WaitStatus::Stopped(thread, signal) => {
+                    self.pending_signal = Some(signal);
+                    (thread, DebugEvent::Signal { signal, thread })
+                }
+                WaitStatus::PtraceEvent(thread, _, event) => {
+                    match self.ptrace_event(thread, event)? {
+                        Some(event) => (thread, event),
+                        None => continue,
+                    }
+                }
+                WaitStatus::PtraceSyscall(_)
+                | WaitStatus::Continued(_)
+                | WaitStatus::StillAlive => continue,
+            };
+
+            self.event_thread = thread;
+            return Ok(event);
+        }
+    }
+
+    /// Waits for a status of any thread of the debuggee, including new threads and forked children
+    /// which haven't been reported by their parent yet.
+    /// Statuses of other children of this thread, e.g. processes spawned by the debugger or the
+    /// debuggees of other targets, are left to be waited for by their owners.
+    pub(super) fn wait_any(&self) -> Result<WaitStatus, Box<dyn std::error::Error>> {
+        loop {
+            let pid = peek_child()?;
+            if self.is_debuggee(pid) {
+                return Ok(waitpid(pid, Some(WaitPidFlag::__WALL))?);
+            }
+            // The status of another child keeps being peeked until its owner waits for it, so the
+            // threads of the debuggee are polled in the meantime.
+            for &thread in &self.threads {
+                match waitpid(thread, Some(WaitPidFlag::WNOHANG | WaitPidFlag::__WALL))? {
+                    WaitStatus::StillAlive => {}
+                    status => return Ok(status),
+                }
+            }
+            std::thread::sleep(FOREIGN_STATUS_POLL_INTERVAL);
+        }
+    }
+
+    fn is_debuggee(&self, pid: Pid) -> bool {
+        self.threads.contains(&pid)
+            || self.forked_children.contains(&pid)
+            || self.is_own_thread(pid)
+            || self.is_own_child(pid)
+    }
+
+    /// Checks whether `pid` is a child process which has been forked by the debuggee.
+    fn is_own_child(&self, pid: Pid) -> bool {
+        // The parent pid follows the state, see `thread::parse_state`.
+        let ppid = std::fs::read_to_string(format!("/proc/{}/stat", pid))
+            .ok()
+            .and_then(|stat| {
+                let (_, rest) = stat.rsplit_once(')')?;
+                rest.split_whitespace().nth(1)?.parse().ok()
+            });
+        ppid == Some(self.pid.as_raw())
+    }
+
+    /// Checks whether `tid` is a thread of the debuggee rather than a separate process.
+    fn is_own_thread(&self, tid: Pid) -> bool {
+        Path::new(&format!("/proc/{}/task/{}", self.pid, tid)).exists()
+    }
+
+    /// Decodes the reason of a `SIGTRAP` stop.
+    pub(super) fn trap_event(
+        &mut self,
+        thread: Pid,
+    ) -> Result<DebugEvent, Box<dyn std::error::Error>> {
+        let siginfo = ptrace::getsiginfo(thread)?;
+        match siginfo.si_code {
+            // `int3` is reported as a `SIGTRAP` sent by the kernel.
+            libc::SI_KERNEL => {
+                // The instruction pointer is left right after the `int3` instruction, so we need
+                // to rewind it to the breakpoint address.
+                let mut regs = ptrace::getregs(thread)?;
+                let addr = (regs.rip as usize).wrapping_sub(1);
+                if let Some(true) = self
+                    .breakpoints
+                    .find_by_addr(addr)
+                    .map(|breakpoint| breakpoint.is_enabled())
+                {
+                    regs.rip = addr as u64;
+                    ptrace::setregs(thread, regs)?;
+                    return Ok(DebugEvent::BreakpointHit { addr, thread });
+                }
+            }
+            libc::TRAP_TRACE => return Ok(DebugEvent::SingleStep { thread }),
+            watchpoint::TRAP_HWBKPT => {
+                if let Some(event) = self.watchpoint_event(thread)? {
+                    return Ok(event);
+                }
+            }
+            _ => {}
+        }
+
+        // Not caused by us, e.g. an `int3` compiled into the debuggee or `kill(SIGTRAP)`.
+        self.pending_signal = Some(Signal::SIGTRAP);
+        Ok(DebugEvent::Signal {
+            signal: Signal::SIGTRAP,
+            thread,
+        })
+    }
+
+    /// Handles a `PTRACE_EVENT_*` stop.
+    /// Returns `None` if the event is not reported to the user.
+    fn ptrace_event(
+        &mut self,
+        thread: Pid,
+        event: libc::c_int,
+    ) -> Result<Option<DebugEvent>, Box<dyn std::error::Error>> {
+        match event {
+            libc::PTRACE_EVENT_CLONE => {
+                let new_thread = Pid::from_raw(ptrace::getevent(thread)? as libc::pid_t);
+                if !self.threads.contains(&new_thread) {
+                    // Consume the initial `SIGSTOP` of the new thread and let it run.
+                    waitpid(new_thread, Some(WaitPidFlag::__WALL))?;
+                    if !self.watchpoints.is_empty() {
+                        self.set_debug_registers(new_thread)?;
+                    }
+                    self.threads.push(new_thread);
+                    ptrace::cont(new_thread, None)?;
+                }
+                Ok(Some(DebugEvent::ThreadCreated(new_thread)))
+            }
+            libc::PTRACE_EVENT_FORK | libc::PTRACE_EVENT_VFORK => {
+                // The child is traced automatically. Wait for its initial stop and let it go.
+                let child = Pid::from_raw(ptrace::getevent(thread)? as libc::pid_t);
+                if let Some(index) = self.forked_children.iter().position(|&pid| pid == child) {
+                    self.forked_children.remove(index);
+                } else {
+                    waitpid(child, Some(WaitPidFlag::__WALL))?;
+                    ptrace::detach(child, None)?;
+                }
+                Ok(Some(DebugEvent::Fork(child)))
+            }
+            libc::PTRACE_EVENT_EXEC => {
+                // `execve` replaces the code and kills all threads other than the main one.
+                self.breakpoints.clear();
+                self.watchpoints.clear();
+                self.region_watchpoints.clear();
+                self.threads = vec![self.pid];
+                Ok(Some(DebugEvent::Exec))
+            }
+            _ => {
+                ptrace::cont(thread, None)?;
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// Waits until a child of this thread has a status, and returns its pid without consuming the
+/// status. The debuggee is traced by this thread, so children of other threads of the debugger
+/// are not considered.
+fn peek_child() -> Result<Pid, Box<dyn std::error::Error>> {
+    let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+    let res = unsafe {
+        libc::waitid(
+            libc::P_ALL,
+            0,
+            &mut info,
+            libc::WEXITED | libc::WSTOPPED | libc::WNOWAIT | libc::__WALL | libc::__WNOTHREAD,
+        )
+    };
+    nix::errno::Errno::result(res)?;
+    Ok(Pid::from_raw(unsafe { info.si_pid() }))
+}
@@ -0,0 +1,197 @@
+use nix::{
+    sys::{
+        ptrace,
+        signal::{kill, Signal},
+        wait::{waitpid, WaitPidFlag, WaitStatus},
+    },
+    unistd::Pid,
+};
+
+use super::{DebugEvent, LinuxTarget, ThreadState};
+
+/// Describes what happens to a debuggee when its `LinuxTarget` is dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DropPolicy {
+    /// The debuggee is killed. This is the default for launched debuggees.
+    Kill,
+    /// The debuggee is detached and keeps running, see `LinuxTarget::detach`. This is the default
+    /// for attached debuggees.
+    Detach,
+    /// Nothing is done, so the debuggee stays traced until this process exits.
+    Leave,
+}
+
+/// Describes how a debuggee has terminated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitStatus {
+    /// The debuggee has exited with the given exit code.
+    Exited(i32),
+    /// The debuggee has been terminated by the given signal.
+    Killed(Signal),
+}
+
+impl LinuxTarget {
+    /// Returns how the debuggee has terminated, once it has been reported by `next_event`.
+    pub fn exit_status(&self) -> Option<ExitStatus> {
+        self.exit_status
+    }
+
+    /// Sets what happens to the debuggee when this target is dropped.
+    pub fn set_drop_policy(&mut self, policy: DropPolicy) {
+        self.drop_policy = policy;
+    }
+
+    /// Stops debugging and lets the debuggee run on its own.
+    /// Breakpoints and watchpoints are removed first, and a pending signal is delivered.
+    /// The debuggee has to be stopped, e.g. because it has reported a debug event.
+    pub fn detach(mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.detach_threads()
+    }
+
+    /// Kills the debuggee with `SIGKILL` and waits for it to terminate.
+    /// Returns how the debuggee has terminated, as it could have exited in the meantime.
+    pub fn kill(mut self) -> Result<ExitStatus, Box<dyn std::error::Error>> {
+        self.kill_threads()
+    }
+
+    fn detach_threads(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if self.exit_status.is_some() {
+            return Err("The debuggee has terminated".into());
+        }
+
+        // The signals which are delivered to the threads when they are detached.
+        let mut signals = Vec::new();
+        if self.thread_state(self.event_thread) == Some(ThreadState::TracingStop) {
+            signals.push((self.event_thread, self.pending_signal.take()));
+        }
+
+        // Stop all threads, as the code can only be restored while none of them is running.
+        for thread in self.threads.clone() {
+            if self.has_deferred_status(thread) {
+                continue;
+            }
+            // Threads can be stopped by an event which hasn't been waited for yet.
+            let stopped = self.thread_state(thread) == Some(ThreadState::TracingStop);
+            match waitpid(thread, Some(WaitPidFlag::WNOHANG | WaitPidFlag::__WALL))? {
+                WaitStatus::StillAlive if stopped => {}
+                WaitStatus::StillAlive => {
+                    self.stop_thread(thread)?;
+                }
+                status => self.deferred_statuses.push(status),
+            }
+        }
+        for status in std::mem::take(&mut self.deferred_statuses) {
+            match status {
+                WaitStatus::Exited(thread, _) | WaitStatus::Signaled(thread, _, _) => {
+                    self.threads.retain(|&tid| tid != thread);
+                    self.stopping_threads.retain(|&tid| tid != thread);
+                }
+                WaitStatus::Stopped(thread, Signal::SIGTRAP) => {
+                    // Rewinds the instruction pointer of breakpoint hits.
+                    if let DebugEvent::Signal { signal, .. } = self.trap_event(thread)? {
+                        signals.push((thread, Some(signal)));
+                    }
+                }
+                WaitStatus::Stopped(thread, Signal::SIGSTOP)
+                    if self.stopping_threads.contains(&thread) =>
+                {
+                    self.stopping_threads.retain(|&tid| tid != thread);
+                }
+                WaitStatus::Stopped(thread, signal) => signals.push((thread, Some(signal))),
+                _ => {}
+            }
+        }
+        if !self.threads.contains(&self.event_thread) {
+            self.event_thread = *self.threads.first().ok_or("The debuggee has terminated")?;
+        }
+
+        // Restore the original code, memory protection and debug registers.
+        let addrs: Vec<_> = self
+            .breakpoints
+            .get_all()
+            .iter()
+            .map(|breakpoint| breakpoint.addr())
+            .collect();
+        if let Err(mut errors) = self.breakpoints.remove(&addrs) {
+            return Err(Box::new(errors.remove(0)));
+        }
+        while let Some(watchpoint) = self.region_watchpoints.first() {
+            self.remove_region_watchpoint(watchpoint.range().start)?;
+        }
+        self.watchpoints.clear();
+        for &thread in &self.threads {
+            self.set_debug_registers(thread)?;
+        }
+
+        // Threads which have reported another status while we were stopping them have to be
+        // resumed until our `SIGSTOP` arrives, as it would stop the debuggee after detaching
+        // otherwise.
+        for thread in std::mem::take(&mut self.stopping_threads) {
+            let mut signal = take_signal(&mut signals, thread);
+            loop {
+                ptrace::cont(thread, signal)?;
+                match waitpid(thread, Some(WaitPidFlag::__WALL))? {
+                    WaitStatus::Stopped(_, Signal::SIGSTOP) => break,
+                    WaitStatus::Exited(..) | WaitStatus::Signaled(..) => {
+                        self.threads.retain(|&tid| tid != thread);
+                        break;
+                    }
+                    WaitStatus::Stopped(_, stop_signal) => signal = Some(stop_signal),
+                    _ => signal = None,
+                }
+            }
+        }
+
+        for thread in std::mem::take(&mut self.threads) {
+            ptrace::detach(thread, take_signal(&mut signals, thread))?;
+        }
+        self.drop_policy = DropPolicy::Leave;
+        Ok(())
+    }
+
+    fn kill_threads(&mut self) -> Result<ExitStatus, Box<dyn std::error::Error>> {
+        if let Some(status) = self.exit_status {
+            return Ok(status);
+        }
+
+        kill(self.pid, Signal::SIGKILL)?;
+        // The status of the main thread is reported once all other threads have been reaped.
+        let status = loop {
+            let status = match self.deferred_statuses.pop() {
+                Some(status) => status,
+                None => self.wait_any()?,
+            };
+            match status {
+                WaitStatus::Exited(pid, code) if pid == self.pid => break ExitStatus::Exited(code),
+                WaitStatus::Signaled(pid, signal, _) if pid == self.pid => {
+                    break ExitStatus::Killed(signal)
+                }
+                _ => {}
+            }
+        };
+        self.exit_status = Some(status);
+        Ok(status)
+    }
+}
+
+impl Drop for LinuxTarget {
+    fn drop(&mut self) {
+        // The pid could have been reused by another process once the debuggee has terminated.
+        if self.exit_status.is_some() {
+            return;
+        }
+        // Errors can't be reported here. The debuggee is detached automatically once this
+        // process exits anyway.
+        let _ = match self.drop_policy {
+            DropPolicy::Kill => self.kill_threads().map(|_| ()),
+            DropPolicy::Detach => self.detach_threads(),
+            DropPolicy::Leave => Ok(()),
+        };
+    }
+}
+
+/// Removes the signal which is delivered to `thread` from `signals`.
+fn take_signal(signals: &mut Vec<(Pid, Option<Signal>)>, thread: Pid) -> Option<Signal> {
+    let index = signals.iter().position(|&(tid, _)| tid == thread)?;
+    signals.remove(index).1
+}
@@ -0,0 +1,123 @@
+use nix::unistd::Pid;
+use std::ops::Range;
+
+use super::region_watchpoint::page_size;
+use crate::symbol::Dwarf;
+
+/// A mapping in the virtual address space of a process, as listed in `/proc/<pid>/maps`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryMap {
+    pub range: Range<usize>,
+    pub is_read: bool,
+    pub is_write: bool,
+    pub is_exec: bool,
+    /// The mapped file, or a pseudo-path like `[stack]` or `[heap]`. `None` for anonymous
+    /// mappings.
+    pub path: Option<String>,
+}
+
+/// Reads the memory maps of a process, sorted by address.
+pub(super) fn memory_maps(pid: Pid) -> Result<Vec<MemoryMap>, Box<dyn std::error::Error>> {
+    std::fs::read_to_string(format!("/proc/{}/maps", pid))?
+        .lines()
+        .map(parse_map)
+        .collect()
+}
+
+/// Finds the mapping which contains `addr` in a list of maps sorted by address.
+pub(super) fn find_map(maps: &[MemoryMap], addr: usize) -> Option<&MemoryMap> {
+    let index = maps.partition_point(|map| map.range.start <= addr);
+    let map = maps.get(index.checked_sub(1)?)?;
+    if !map.range.contains(&addr) {
+        return None;
+    }
+    Some(map)
+}
+
+/// Computes the load bias of the module described by `debuginfo`, see `LinuxTarget::load_bias`.
+pub(super) fn load_bias(
+    maps: &[MemoryMap],
+    debuginfo: &Dwarf,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let path = debuginfo.path().to_string_lossy();
+    // The lowest mapping of a module maps the start of its file.
+    let map = maps
+        .iter()
+        .find(|map| map.path.as_deref() == Some(&*path))
+        .ok_or_else(|| format!("{} is not loaded by the debuggee", path))?;
+    let load_address = debuginfo.get_load_address() as usize & !(page_size() - 1);
+    Ok(map.range.start.wrapping_sub(load_address))
+}
+
+// Lines look like `00400000-00452000 r-xp 00000000 08:02 173521 /usr/bin/dbus-daemon`.
+fn parse_map(line: &str) -> Result<MemoryMap, Box<dyn std::error::Error>> {
+    let mut fields = line.splitn(6, ' ');
+    let mut next_field = || {
+        fields
+            .next()
+            .ok_or_else(|| format!("Invalid memory map: {}", line))
+    };
+
+    let range = next_field()?;
+    let perms = next_field()?.as_bytes();
+    // Offset, device and inode.
+    for _ in 0..3 {
+        next_field()?;
+    }
+    let path = fields.next().map(str::trim).filter(|path| !path.is_empty());
+
+    let mut bounds = range.split('-');
+    let start = usize::from_str_radix(bounds.next().unwrap_or(""), 16)?;
+    let end = usize::from_str_radix(bounds.next().unwrap_or(""), 16)?;
+    if perms.len() < 3 {
+        return Err(format!("Invalid memory map: {}", line).into());
+    }
+
+    Ok(MemoryMap {
+        range: start..end,
+        is_read: perms[0] == b'r',
+        is_write: perms[1] == b'w',
+        is_exec: perms[2] == b'x',
+        path: path.map(str::to_string),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_map, parse_map, MemoryMap};
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            parse_map("00400000-00452000 r-xp 00000000 08:02 173521      /usr/bin/dbus-daemon")
+                .unwrap(),
+            MemoryMap {
+                range: 0x400000..0x452000,
+                is_read: true,
+                is_write: false,
+                is_exec: true,
+                path: Some("/usr/bin/dbus-daemon".to_string()),
+            }
+        );
+        assert_eq!(
+            parse_map("7ffd2f1b4000-7ffd2f1d5000 rw-p 00000000 00:00 0 ")
+                .unwrap()
+                .path,
+            None
+        );
+        assert!(parse_map("garbage").is_err());
+    }
+
+    #[test]
+    fn find() {
+        let maps = [
+            parse_map("1000-2000 r-xp 00000000 00:00 0").unwrap(),
+            parse_map("3000-4000 rw-p 00000000 00:00 0").unwrap(),
+        ];
+        assert_eq!(find_map(&maps, 0x1000), Some(&maps[0]));
+        assert_eq!(find_map(&maps, 0x3fff), Some(&maps[1]));
+        assert_eq!(find_map(&maps, 0x2000), None);
+        assert_eq!(find_map(&maps, 0x4000), None);
+        assert_eq!(find_map(&maps, 0), None);
+    }
+}
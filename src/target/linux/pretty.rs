@@ -0,0 +1,389 @@
+mod std_types;
+
+use super::{LinuxTarget, Value, ValueKind};
+use crate::symbol::{Dwarf, TypeId, TypeKind};
+
+/// The maximum number of bytes of a string which are read from the debuggee.
+const MAX_STRING_LENGTH: usize = 4096;
+
+/// Renders values of a type in a custom way, e.g. a collection as its elements rather than its
+/// internal structure. Printers are registered on a `ValueFormatter` for the types whose names
+/// match a pattern.
+///
+/// Closures taking a value and a `FormatContext` are printers as well.
+pub trait PrettyPrinter {
+    /// Renders `value`, which can be read further from the debuggee using `context`.
+    /// Returning an error, e.g. because the value doesn't have the expected layout, makes the
+    /// formatter fall back to the next matching printer, or to the structure of the value.
+    fn format(
+        &self,
+        value: &Value,
+        context: &FormatContext,
+    ) -> Result<String, Box<dyn std::error::Error>>;
+}
+
+impl<F> PrettyPrinter for F
+where
+    F: Fn(&Value, &FormatContext) -> Result<String, Box<dyn std::error::Error>>,
+{
+    fn format(
+        &self,
+        value: &Value,
+        context: &FormatContext,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        self(value, context)
+    }
+}
+
+/// A pattern matching the names of types, in which `*` matches any sequence of characters,
+/// e.g. `SmallVec<*>` or `Graph<*`.
+struct TypePattern {
+    parts: Vec<String>,
+}
+
+impl TypePattern {
+    fn new(pattern: &str) -> TypePattern {
+        TypePattern {
+            parts: pattern.split('*').map(str::to_string).collect(),
+        }
+    }
+
+    fn matches(&self, type_name: &str) -> bool {
+        let (first, rest) = match self.parts.split_first() {
+            Some(split) => split,
+            None => return false,
+        };
+        let mut remaining = match type_name.strip_prefix(first.as_str()) {
+            Some(remaining) => remaining,
+            None => return false,
+        };
+        let (last, middle) = match rest.split_last() {
+            Some(split) => split,
+            // The pattern has no wildcard.
+            None => return remaining.is_empty(),
+        };
+        for part in middle {
+            match remaining.find(part.as_str()) {
+                Some(index) => remaining = &remaining[index + part.len()..],
+                None => return false,
+            }
+        }
+        remaining.ends_with(last.as_str())
+    }
+}
+
+/// Renders values read by `LinuxTarget::read_value` as text similar to their `Debug`
+/// representation.
+///
+/// Collections and smart pointers of the standard library, e.g. `Vec`, `String`, `HashMap` or
+/// `Rc`, are rendered as their logical contents rather than their internal structure. Values
+/// whose layout isn't recognized, e.g. because it has changed in a newer version of the standard
+/// library, are rendered as their raw structure instead. Other types can be rendered by
+/// registering a `PrettyPrinter`.
+pub struct ValueFormatter<'a> {
+    target: &'a LinuxTarget,
+    debuginfo: &'a Dwarf,
+    max_elements: usize,
+    max_depth: usize,
+    printers: Vec<(TypePattern, Box<dyn PrettyPrinter + 'a>)>,
+}
+
+impl<'a> ValueFormatter<'a> {
+    pub fn new(target: &'a LinuxTarget, debuginfo: &'a Dwarf) -> ValueFormatter<'a> {
+        ValueFormatter {
+            target,
+            debuginfo,
+            max_elements: 100,
+            max_depth: 8,
+            printers: Vec::new(),
+        }
+    }
+
+    /// Sets the maximum number of elements of arrays and collections which are rendered.
+    pub fn max_elements(mut self, max_elements: usize) -> Self {
+        self.max_elements = max_elements;
+        self
+    }
+
+    /// Sets the maximum nesting of values which are rendered, including the values behind
+    /// pointers.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Renders the values whose type names match `pattern` with `printer`. A `*` in the pattern
+    /// matches any sequence of characters, e.g. `SmallVec<*>`.
+    ///
+    /// Type names are matched as they appear in the debug info, e.g. `Graph<u32>` for structs,
+    /// which are named without their path, and `alloc::boxed::Box<u32, alloc::alloc::Global>`
+    /// for boxes. Printers registered later take precedence over the ones registered earlier,
+    /// and all of them take precedence over the built-in printers.
+    pub fn register(&mut self, pattern: &str, printer: impl PrettyPrinter + 'a) {
+        self.printers
+            .push((TypePattern::new(pattern), Box::new(printer)));
+    }
+
+    /// Renders a value. Parts of the value which can't be read from the debuggee are rendered
+    /// as `<error: ...>`.
+    pub fn format(&self, value: &Value) -> String {
+        self.format_value(value, 0)
+    }
+
+    fn format_value(&self, value: &Value, depth: usize) -> String {
+        if depth > self.max_depth {
+            return "..".to_string();
+        }
+        let context = FormatContext {
+            formatter: self,
+            depth,
+        };
+        if let Some(type_name) = value.type_name.as_deref() {
+            for (pattern, printer) in self.printers.iter().rev() {
+                if pattern.matches(type_name) {
+                    if let Ok(text) = printer.format(value, &context) {
+                        return text;
+                    }
+                }
+            }
+        }
+        if let Some(printer) = std_types::std_printer(self.debuginfo, value) {
+            if let Ok(text) = printer(value, &context) {
+                return text;
+            }
+        }
+        context.format_raw(value)
+    }
+}
+
+/// Gives pretty printers access to the debuggee, and renders the values nested in the value
+/// being rendered.
+pub struct FormatContext<'f, 'a> {
+    formatter: &'f ValueFormatter<'a>,
+    depth: usize,
+}
+
+impl<'f, 'a> FormatContext<'f, 'a> {
+    pub fn target(&self) -> &'a LinuxTarget {
+        self.formatter.target
+    }
+
+    pub fn debuginfo(&self) -> &'a Dwarf {
+        self.formatter.debuginfo
+    }
+
+    /// Returns the maximum number of elements of collections which should be rendered.
+    pub fn max_elements(&self) -> usize {
+        self.formatter.max_elements
+    }
+
+    /// Renders a value nested in the value being rendered, e.g. an element of a collection.
+    pub fn format(&self, value: &Value) -> String {
+        self.formatter.format_value(value, self.depth + 1)
+    }
+
+    /// Renders the elements of a collection of `len` elements, of which only `elements` have
+    /// been read, e.g. `[1, 2, ..]`.
+    pub fn format_elements(
+        &self,
+        open: &str,
+        elements: &[Value],
+        len: usize,
+        close: &str,
+    ) -> String {
+        let elements = elements.iter().map(|element| self.format(element));
+        format_list(open, elements, len, close)
+    }
+
+    /// Returns the type parameter `name` of the type of `value`, e.g. `T` of `Vec<T>`.
+    pub fn template_param(
+        &self,
+        value: &Value,
+        name: &str,
+    ) -> Result<TypeId, Box<dyn std::error::Error>> {
+        match self.debuginfo().get_type(value.type_id)?.kind {
+            TypeKind::Struct(struct_type) => struct_type
+                .template_params
+                .into_iter()
+                .find(|param| param.name.as_deref() == Some(name))
+                .and_then(|param| param.ty)
+                .ok_or_else(|| {
+                    format!("{:?} has no type parameter {}", value.type_name, name).into()
+                }),
+            _ => Err(format!("{:?} is not a generic struct", value.type_name).into()),
+        }
+    }
+
+    /// Returns the size of a type in bytes.
+    pub fn type_size(&self, type_id: TypeId) -> Result<usize, Box<dyn std::error::Error>> {
+        let ty = self.debuginfo().get_type(type_id)?;
+        ty.size
+            .map(|size| size as usize)
+            .ok_or_else(|| format!("The size of {:?} is unknown", ty.name).into())
+    }
+
+    /// Reads up to `max_elements` of `len` consecutive elements of the type `element`.
+    pub fn read_elements(
+        &self,
+        element: TypeId,
+        address: usize,
+        len: usize,
+    ) -> Result<Vec<Value>, Box<dyn std::error::Error>> {
+        let size = self.type_size(element)?;
+        (0..len.min(self.max_elements()))
+            .map(|index| {
+                let address = element_address(address, index, size)?;
+                self.target()
+                    .read_value_at(self.debuginfo(), element, address)
+            })
+            .collect()
+    }
+
+    /// Reads `len` bytes from the memory of the debuggee.
+    pub fn read_bytes(
+        &self,
+        address: usize,
+        len: usize,
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let mut bytes = vec![0; len];
+        self.target()
+            .read()
+            .read_slice(&mut bytes, address)
+            .apply_all()?;
+        Ok(bytes)
+    }
+
+    fn read_uint(&self, address: usize, size: usize) -> Result<u64, Box<dyn std::error::Error>> {
+        let mut bytes = [0u8; 8];
+        let value = self.read_bytes(address, size.min(bytes.len()))?;
+        bytes[..value.len()].copy_from_slice(&value);
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    /// Renders `len` bytes of UTF-8 at `address` as a string literal.
+    fn format_str(&self, address: usize, len: usize) -> Result<String, Box<dyn std::error::Error>> {
+        let bytes = self.read_bytes(address, len.min(MAX_STRING_LENGTH))?;
+        let mut text = format!("{:?}", String::from_utf8_lossy(&bytes));
+        if len > MAX_STRING_LENGTH {
+            text.push_str("..");
+        }
+        Ok(text)
+    }
+
+    /// Renders the structure of a value as described by the debug info.
+    fn format_raw(&self, value: &Value) -> String {
+        let type_name = value.type_name.as_deref().unwrap_or("");
+        match &value.kind {
+            ValueKind::Bool(value) => value.to_string(),
+            ValueKind::Int(value) => value.to_string(),
+            ValueKind::UInt(value) => value.to_string(),
+            ValueKind::Float(value) => format!("{:?}", value),
+            ValueKind::Char(value) => format!("{:?}", value),
+            ValueKind::Struct(fields) | ValueKind::Union(fields) => {
+                self.format_fields(type_name, fields)
+            }
+            ValueKind::Tuple(elements) => self.format_elements("(", elements, elements.len(), ")"),
+            ValueKind::Enum { variant, fields } => {
+                self.format_fields(variant.as_deref().unwrap_or(type_name), fields)
+            }
+            ValueKind::CEnum { value, name } => match name {
+                Some(name) => name.clone(),
+                None => value.to_string(),
+            },
+            ValueKind::Array(elements) => {
+                let shown = &elements[..elements.len().min(self.max_elements())];
+                self.format_elements("[", shown, elements.len(), "]")
+            }
+            // References are rendered as the value they point to, like `Debug` does.
+            ValueKind::Pointer { address, .. } if type_name.starts_with('&') && *address != 0 => {
+                match self.target().read_pointee(self.debuginfo(), value) {
+                    Ok(pointee) => self.format(&pointee),
+                    Err(err) => format_error(err),
+                }
+            }
+            ValueKind::Pointer { address, .. } => format!("{:#x}", address),
+            ValueKind::Opaque(bytes) => format!("<{} bytes>", bytes.len()),
+        }
+    }
+
+    /// Renders a struct or an enum variant. Fields named `__0`, `__1` and so on are rendered
+    /// like the fields of a tuple struct.
+    fn format_fields(&self, name: &str, fields: &[(Option<String>, Value)]) -> String {
+        if fields.is_empty() {
+            return name.to_string();
+        }
+        let is_tuple = fields.iter().all(|(field, _)| {
+            field
+                .as_deref()
+                .is_some_and(|field| field.starts_with("__"))
+        });
+        let len = fields.len();
+        if is_tuple {
+            let fields = fields.iter().map(|(_, value)| self.format(value));
+            return format_list(&format!("{}(", name), fields, len, ")");
+        }
+        let fields = fields.iter().map(|(field, value)| {
+            format!(
+                "{}: {}",
+                field.as_deref().unwrap_or("_"),
+                self.format(value)
+            )
+        });
+        format_list(&format!("{} {{ ", name), fields, len, " }")
+    }
+}
+
+/// Joins rendered elements, indicating the elements which aren't shown.
+fn format_list(
+    open: &str,
+    elements: impl Iterator<Item = String>,
+    len: usize,
+    close: &str,
+) -> String {
+    let mut elements: Vec<_> = elements.collect();
+    if elements.len() < len {
+        elements.push("..".to_string());
+    }
+    format!("{}{}{}", open, elements.join(", "), close)
+}
+
+fn format_error(err: Box<dyn std::error::Error>) -> String {
+    format!("<error: {}>", err)
+}
+
+/// Returns the address of the element at `index` of an array starting at `address`, or an
+/// error if it overflows, as corrupted values can have arbitrary lengths.
+fn element_address(
+    address: usize,
+    index: usize,
+    size: usize,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    index
+        .checked_mul(size)
+        .and_then(|offset| address.checked_add(offset))
+        .ok_or_else(|| "The address of an element overflows".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{element_address, TypePattern};
+
+    #[test]
+    fn type_patterns() {
+        assert!(TypePattern::new("Graph").matches("Graph"));
+        assert!(!TypePattern::new("Graph").matches("Graph<u32>"));
+        assert!(TypePattern::new("Graph<*>").matches("Graph<u32>"));
+        assert!(!TypePattern::new("Graph<*>").matches("MyGraph<u32>"));
+        assert!(TypePattern::new("*Graph<*").matches("MyGraph<u32>"));
+        assert!(TypePattern::new("*::Box<*, *>").matches("alloc::boxed::Box<u8, Global>"));
+        assert!(!TypePattern::new("*::Box<*, *>").matches("alloc::boxed::Box<u8>"));
+        assert!(TypePattern::new("*").matches(""));
+    }
+
+    #[test]
+    fn element_addresses() {
+        assert_eq!(element_address(0x1000, 2, 8).unwrap(), 0x1010);
+        assert!(element_address(0x1000, usize::MAX, 8).is_err());
+        assert!(element_address(usize::MAX - 8, 1, 16).is_err());
+    }
+}
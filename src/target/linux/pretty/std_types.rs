@@ -0,0 +1,498 @@
+// Built-in printers for the types of the standard library.
+
+use super::{element_address, format_list, FormatContext, Value, ValueKind};
+use crate::symbol::{Dwarf, Type, TypeId, TypeKind};
+
+/// The maximum height of a `BTreeMap`, which guards against cycles in corrupted trees.
+const MAX_BTREE_HEIGHT: usize = 32;
+/// The maximum number of buckets of a `HashMap` whose control bytes are read, which guards
+/// against huge reads for corrupted tables.
+const MAX_HASH_MAP_BUCKETS: usize = 1 << 24;
+
+/// A built-in printer for a type of the standard library.
+type Printer = fn(&Value, &FormatContext) -> Result<String, Box<dyn std::error::Error>>;
+
+/// The built-in printers by the paths of the types they render. Types which have been moved in
+/// some versions of the standard library are listed with each of their paths.
+const STD_PRINTERS: &[(&str, Printer)] = &[
+    ("alloc::vec::Vec", format_vec),
+    ("alloc::string::String", format_string),
+    ("alloc::boxed::Box", format_box),
+    ("alloc::rc::Rc", format_rc),
+    ("alloc::sync::Arc", format_rc),
+    ("std::collections::hash::map::HashMap", format_hash_map),
+    ("std::collections::hash::set::HashSet", format_hash_map),
+    ("alloc::collections::btree::map::BTreeMap", format_btree_map),
+    ("alloc::collections::btree::set::BTreeSet", format_btree_map),
+    ("alloc::collections::vec_deque::VecDeque", format_vec_deque),
+    ("core::cell::Cell", format_cell),
+    ("core::cell::RefCell", format_cell),
+    ("std::sync::mutex::Mutex", format_mutex),
+    ("std::sync::poison::mutex::Mutex", format_mutex),
+];
+
+/// Returns the built-in printer for a type of the standard library.
+pub(super) fn std_printer(debuginfo: &Dwarf, value: &Value) -> Option<Printer> {
+    let type_name = value.type_name.as_deref()?;
+    if type_name.starts_with("&str") || type_name.starts_with("&mut str") {
+        return Some(format_str_slice);
+    }
+    if type_name.starts_with("&[") || type_name.starts_with("&mut [") {
+        return Some(format_slice);
+    }
+    if type_name.starts_with("&dyn ") || type_name.starts_with("&mut dyn ") {
+        return Some(format_trait_object);
+    }
+    // Types are matched by their path, so that types of other crates which are named like the
+    // ones of the standard library aren't rendered as them. The path is only looked up for
+    // candidates, as it requires searching the debug info.
+    let name = base_name(type_name);
+    if !STD_PRINTERS.iter().any(|(path, _)| base_name(path) == name) {
+        return None;
+    }
+    let qualified_name = debuginfo.get_qualified_type_name(value.type_id).ok()??;
+    let qualified_name = qualified_name
+        .find('<')
+        .map_or(qualified_name.as_str(), |end| &qualified_name[..end]);
+    STD_PRINTERS
+        .iter()
+        .find(|(path, _)| *path == qualified_name)
+        .map(|(_, printer)| *printer)
+}
+
+fn format_str_slice(
+    value: &Value,
+    context: &FormatContext,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let (address, _) = field_pointer(value, "data_ptr")?;
+    let len = field_integer(value, "length")?;
+    context.format_str(address, len)
+}
+
+fn format_slice(
+    value: &Value,
+    context: &FormatContext,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let (address, element) = field_pointer(value, "data_ptr")?;
+    let element = element.ok_or("The type of the slice elements is unknown")?;
+    let len = field_integer(value, "length")?;
+    let elements = context.read_elements(element, address, len)?;
+    Ok(context.format_elements("[", &elements, len, "]"))
+}
+
+fn format_vec(
+    value: &Value,
+    context: &FormatContext,
+) -> Result<String, Box<dyn std::error::Error>> {
+    // The type of the buffer pointer is `u8` in some versions, so the element type is taken from
+    // the type parameter instead.
+    let (address, _) = find_pointer(value).ok_or("Vec without a buffer")?;
+    let len = field_integer(value, "len")?;
+    let element = context.template_param(value, "T")?;
+    let elements = context.read_elements(element, address, len)?;
+    Ok(context.format_elements("[", &elements, len, "]"))
+}
+
+fn format_string(
+    value: &Value,
+    context: &FormatContext,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let (address, _) = find_pointer(value).ok_or("String without a buffer")?;
+    let len = field_integer(value, "len")?;
+    context.format_str(address, len)
+}
+
+fn format_box(
+    value: &Value,
+    context: &FormatContext,
+) -> Result<String, Box<dyn std::error::Error>> {
+    match value.kind {
+        ValueKind::Pointer { .. } => {
+            let pointee = context.target().read_pointee(context.debuginfo(), value)?;
+            Ok(context.format(&pointee))
+        }
+        // Boxed trait objects, slices and strings are fat pointers.
+        _ if value.field("vtable").is_some() => format_trait_object(value, context),
+        _ if value.type_name.as_deref().unwrap_or("").contains("<str") => {
+            format_str_slice(value, context)
+        }
+        _ => format_slice(value, context),
+    }
+}
+
+fn format_trait_object(
+    value: &Value,
+    context: &FormatContext,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let object = context
+        .target()
+        .read_trait_object(context.debuginfo(), value)?;
+    Ok(context.format(&object))
+}
+
+fn format_rc(value: &Value, context: &FormatContext) -> Result<String, Box<dyn std::error::Error>> {
+    let (address, inner) = find_pointer(value).ok_or("Rc without a pointer")?;
+    let inner = inner.ok_or("The type of the Rc allocation is unknown")?;
+    let inner = context
+        .target()
+        .read_value_at(context.debuginfo(), inner, address)?;
+    // The value is named `value` in `Rc` and `data` in `Arc`.
+    let value = inner
+        .field("value")
+        .or_else(|| inner.field("data"))
+        .ok_or("Rc allocation without a value")?;
+    Ok(context.format(value))
+}
+
+fn format_hash_map(
+    value: &Value,
+    context: &FormatContext,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let is_set = value
+        .type_name
+        .as_deref()
+        .is_some_and(|name| base_name(name) == "HashSet");
+    let table = find_value(value, &|value| {
+        value
+            .type_name
+            .as_deref()
+            .is_some_and(|name| base_name(name) == "RawTable")
+    })
+    .ok_or("HashMap without a table")?;
+    // Entries are stored as `(K, V)` tuples, and as `(T, ())` tuples for sets.
+    let entry = context.template_param(table, "T")?;
+    let entry_size = context.type_size(entry)?;
+    let items = field_integer(table, "items")?;
+    let buckets = field_integer(table, "bucket_mask")?
+        .checked_add(1)
+        .filter(|buckets| buckets.is_power_of_two())
+        .ok_or("Invalid number of buckets")?;
+    if buckets > MAX_HASH_MAP_BUCKETS {
+        return Err(format!("HashMap with too many buckets: {}", buckets).into());
+    }
+    let (ctrl, _) = find_field(table, "ctrl")
+        .and_then(find_pointer)
+        .ok_or("HashMap without control bytes")?;
+    // Older versions of hashbrown store the entries in a separate allocation, newer ones right
+    // below the control bytes in reverse order.
+    let data = find_field(table, "data").and_then(find_pointer);
+
+    let mut entries = Vec::new();
+    if items > 0 {
+        // A control byte with the high bit unset marks a full bucket.
+        let ctrl_bytes = context.read_bytes(ctrl, buckets)?;
+        for (index, _) in ctrl_bytes
+            .iter()
+            .enumerate()
+            .filter(|(_, byte)| *byte & 0x80 == 0)
+            .take(context.max_elements())
+        {
+            let address = match data {
+                Some((data, _)) => element_address(data, index, entry_size)?,
+                None => (index + 1)
+                    .checked_mul(entry_size)
+                    .and_then(|offset| ctrl.checked_sub(offset))
+                    .ok_or("The address of a HashMap entry underflows")?,
+            };
+            let entry = context
+                .target()
+                .read_value_at(context.debuginfo(), entry, address)?;
+            entries.push(match entry.kind {
+                ValueKind::Tuple(mut fields) if fields.len() == 2 => {
+                    let value = fields.pop().unwrap();
+                    let key = fields.pop().unwrap();
+                    (key, value)
+                }
+                kind => return Err(format!("Unexpected HashMap entry: {:?}", kind).into()),
+            });
+        }
+    }
+    Ok(format_entries(context, &entries, items, is_set))
+}
+
+fn format_btree_map(
+    value: &Value,
+    context: &FormatContext,
+) -> Result<String, Box<dyn std::error::Error>> {
+    // Sets are maps with values of a zero-sized type.
+    let (map, is_set) = match value.type_name.as_deref().map(base_name) {
+        Some("BTreeSet") => (
+            find_field(value, "map").ok_or("BTreeSet without a map")?,
+            true,
+        ),
+        _ => (value, false),
+    };
+    let key = context.template_param(map, "K")?;
+    let val = context.template_param(map, "V")?;
+    let len = field_integer(map, "length")?;
+
+    let mut entries = Vec::new();
+    // The root is `None` for empty maps.
+    if let (Some(height), Some((node, Some(leaf)))) = (
+        find_field(map, "height").and_then(integer),
+        find_field(map, "node").and_then(find_pointer),
+    ) {
+        let debuginfo = context.debuginfo();
+        let leaf_type = debuginfo.get_type(leaf)?;
+        // Internal nodes start with a leaf node, followed by the edges. They are only described
+        // by the debug info if the tree has grown beyond a single node.
+        let edges = if height > 0 {
+            let leaf_name = debuginfo
+                .get_qualified_type_name(leaf)?
+                .ok_or("BTreeMap node without a name")?;
+            let internal_name = leaf_name.replacen("LeafNode<", "InternalNode<", 1);
+            let internal = debuginfo
+                .find_type(&internal_name)?
+                .ok_or_else(|| format!("{} is not described by the debug info", internal_name))?;
+            node_field(context, &debuginfo.get_type(internal)?, "edges")?.0
+        } else {
+            0
+        };
+        let layout = BTreeNode {
+            key,
+            val,
+            key_size: context.type_size(key)?,
+            val_size: context.type_size(val)?,
+            len: node_field(context, &leaf_type, "len")?,
+            keys: node_field(context, &leaf_type, "keys")?.0,
+            vals: node_field(context, &leaf_type, "vals")?.0,
+            edges,
+        };
+        layout.entries(context, node, height, &mut entries)?;
+    }
+    Ok(format_entries(context, &entries, len, is_set))
+}
+
+/// The layout of the nodes of a `BTreeMap`.
+struct BTreeNode {
+    key: TypeId,
+    val: TypeId,
+    key_size: usize,
+    val_size: usize,
+    /// The offset and the size of the number of entries of a node.
+    len: (usize, usize),
+    keys: usize,
+    vals: usize,
+    edges: usize,
+}
+
+impl BTreeNode {
+    /// Collects the entries of the node at `address` and its children in order.
+    fn entries(
+        &self,
+        context: &FormatContext,
+        address: usize,
+        height: usize,
+        entries: &mut Vec<(Value, Value)>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if height > MAX_BTREE_HEIGHT {
+            return Err("BTreeMap is too high".into());
+        }
+        let len = context.read_uint(address + self.len.0, self.len.1)? as usize;
+        for index in 0..=len {
+            if entries.len() >= context.max_elements() {
+                break;
+            }
+            if height > 0 {
+                let edge = context.read_uint(address + self.edges + index * 8, 8)?;
+                self.entries(context, edge as usize, height - 1, entries)?;
+            }
+            if index < len && entries.len() < context.max_elements() {
+                let target = context.target();
+                let key_address = address + self.keys + index * self.key_size;
+                let val_address = address + self.vals + index * self.val_size;
+                entries.push((
+                    target.read_value_at(context.debuginfo(), self.key, key_address)?,
+                    target.read_value_at(context.debuginfo(), self.val, val_address)?,
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the offset and the size of a field of a `BTreeMap` node.
+fn node_field(
+    context: &FormatContext,
+    node: &Type,
+    name: &str,
+) -> Result<(usize, usize), Box<dyn std::error::Error>> {
+    let fields = match &node.kind {
+        TypeKind::Struct(node) => &node.fields,
+        _ => return Err("BTreeMap node is not a struct".into()),
+    };
+    let field = fields
+        .iter()
+        .find(|field| field.name.as_deref() == Some(name))
+        .ok_or_else(|| format!("BTreeMap node without {}", name))?;
+    let offset = field
+        .offset
+        .ok_or_else(|| format!("The offset of {} is unknown", name))?;
+    let ty = field
+        .ty
+        .ok_or_else(|| format!("The type of {} is unknown", name))?;
+    Ok((offset as usize, context.type_size(ty)?))
+}
+
+fn format_vec_deque(
+    value: &Value,
+    context: &FormatContext,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let buf = find_field(value, "buf").ok_or("VecDeque without a buffer")?;
+    let (address, _) = find_pointer(buf).ok_or("VecDeque without a buffer")?;
+    let capacity = field_integer(buf, "cap")?;
+    let head = field_integer(value, "head")?;
+    // Older versions store the range of the elements in the ring buffer as `tail..head`, with a
+    // capacity which is a power of two.
+    let (start, len) = match find_field(value, "len").and_then(integer) {
+        Some(len) => (head, len),
+        None => {
+            let tail = field_integer(value, "tail")?;
+            (tail, head.wrapping_sub(tail) & capacity.wrapping_sub(1))
+        }
+    };
+    if len > 0 && capacity == 0 {
+        return Err("VecDeque without capacity".into());
+    }
+
+    let element = context.template_param(value, "T")?;
+    let size = context.type_size(element)?;
+    let elements = (0..len.min(context.max_elements()))
+        .map(|index| {
+            let index = start.checked_add(index).ok_or("Invalid VecDeque range")? % capacity;
+            let address = element_address(address, index, size)?;
+            context
+                .target()
+                .read_value_at(context.debuginfo(), element, address)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(context.format_elements("[", &elements, len, "]"))
+}
+
+fn format_cell(
+    value: &Value,
+    context: &FormatContext,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let name = base_name(value.type_name.as_deref().unwrap_or(""));
+    let inner = unsafe_cell_value(value.field("value").ok_or("Cell without a value")?);
+    Ok(format!("{} {{ value: {} }}", name, context.format(inner)))
+}
+
+fn format_mutex(
+    value: &Value,
+    context: &FormatContext,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let data = unsafe_cell_value(value.field("data").ok_or("Mutex without data")?);
+    let poisoned = find_field(value, "failed")
+        .and_then(integer)
+        .ok_or("Mutex without a poison flag")?;
+    Ok(format!(
+        "Mutex {{ data: {}, poisoned: {}, .. }}",
+        context.format(data),
+        poisoned != 0
+    ))
+}
+
+/// Renders the entries of a map, or the keys of a set.
+fn format_entries(
+    context: &FormatContext,
+    entries: &[(Value, Value)],
+    len: usize,
+    is_set: bool,
+) -> String {
+    let entries = entries.iter().map(|(key, value)| {
+        let key = context.format(key);
+        if is_set {
+            key
+        } else {
+            format!("{}: {}", key, context.format(value))
+        }
+    });
+    format_list("{", entries, len, "}")
+}
+
+/// Returns the name of a type without its path and generic arguments, e.g. `Vec` for
+/// `alloc::vec::Vec<u8, alloc::alloc::Global>`.
+fn base_name(type_name: &str) -> &str {
+    let end = type_name.find('<').unwrap_or(type_name.len());
+    type_name[..end].rsplit("::").next().unwrap_or("")
+}
+
+/// Returns the value inside of an `UnsafeCell`, or `value` itself if it isn't one.
+fn unsafe_cell_value(value: &Value) -> &Value {
+    match value.type_name.as_deref().map(base_name) {
+        Some("UnsafeCell") => value.field("value").unwrap_or(value),
+        _ => value,
+    }
+}
+
+/// Finds the first value within `value` matching `predicate`, searching the fields of structs
+/// depth-first. Values behind pointers are not searched.
+fn find_value<'v>(value: &'v Value, predicate: &dyn Fn(&Value) -> bool) -> Option<&'v Value> {
+    if predicate(value) {
+        return Some(value);
+    }
+    match &value.kind {
+        ValueKind::Struct(fields) | ValueKind::Enum { fields, .. } => fields
+            .iter()
+            .find_map(|(_, value)| find_value(value, predicate)),
+        _ => None,
+    }
+}
+
+/// Finds the field `name` of `value` or of one of the structs nested in it. Fields are looked up
+/// by name rather than by their position, as the internal structure of the types of the
+/// standard library changes between versions.
+fn find_field<'v>(value: &'v Value, name: &str) -> Option<&'v Value> {
+    match &value.kind {
+        ValueKind::Struct(fields) | ValueKind::Enum { fields, .. } => fields
+            .iter()
+            .find(|(field, _)| field.as_deref() == Some(name))
+            .map(|(_, value)| value)
+            .or_else(|| fields.iter().find_map(|(_, value)| find_field(value, name))),
+        _ => None,
+    }
+}
+
+/// Returns the first pointer within `value`, unwrapping wrappers like `NonNull` or `Unique`.
+fn find_pointer(value: &Value) -> Option<(usize, Option<TypeId>)> {
+    match find_value(value, &|value| {
+        matches!(value.kind, ValueKind::Pointer { .. })
+    })?
+    .kind
+    {
+        ValueKind::Pointer { address, pointee } => Some((address, pointee)),
+        _ => None,
+    }
+}
+
+/// Returns the first integer within `value`, unwrapping wrappers like `Cell` or `AtomicUsize`.
+fn integer(value: &Value) -> Option<usize> {
+    let value = find_value(value, &|value| {
+        matches!(
+            value.kind,
+            ValueKind::UInt(_) | ValueKind::Int(_) | ValueKind::Bool(_)
+        )
+    })?;
+    match value.kind {
+        ValueKind::UInt(value) => Some(value as usize),
+        ValueKind::Int(value) => Some(value as usize),
+        ValueKind::Bool(value) => Some(value as usize),
+        _ => None,
+    }
+}
+
+fn field_integer(value: &Value, name: &str) -> Result<usize, Box<dyn std::error::Error>> {
+    find_field(value, name)
+        .and_then(integer)
+        .ok_or_else(|| format!("{:?} without {}", value.type_name, name).into())
+}
+
+fn field_pointer(
+    value: &Value,
+    name: &str,
+) -> Result<(usize, Option<TypeId>), Box<dyn std::error::Error>> {
+    find_field(value, name)
+        .and_then(find_pointer)
+        .ok_or_else(|| format!("{:?} without {}", value.type_name, name).into())
+}
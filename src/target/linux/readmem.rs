@@ -0,0 +1,388 @@
+use nix::{sys::ptrace, unistd::Pid};
+use std::{fs::File, marker::PhantomData, mem, os::unix::fs::FileExt};
+
+use crate::target::unix::ReadError;
+
+/// A single memory read operation.
+struct ReadOp {
+    // Remote memory location.
+    remote_base: usize,
+    // Size of the `local_ptr` buffer.
+    len: usize,
+    // Pointer to a local destination buffer.
+    local_ptr: *mut libc::c_void,
+}
+
+impl ReadOp {
+    /// Converts the memory read operation into a remote IoVec.
+    fn as_remote_iovec(&self) -> libc::iovec {
+        libc::iovec {
+            iov_base: self.remote_base as *const libc::c_void as *mut _,
+            iov_len: self.len,
+        }
+    }
+
+    /// Converts the memory read operation into a local IoVec.
+    fn as_local_iovec(&self) -> libc::iovec {
+        libc::iovec {
+            iov_base: self.local_ptr,
+            iov_len: self.len,
+        }
+    }
+
+    /// Returns the local destination buffer.
+    #[allow(clippy::mut_from_ref)]
+    fn buffer(&self) -> &mut [u8] {
+        // Safety: `local_ptr` points to a buffer of `len` bytes, which is exclusively borrowed
+        // by the `ReadMemory` this operation belongs to.
+        unsafe { std::slice::from_raw_parts_mut(self.local_ptr as *mut u8, self.len) }
+    }
+
+    /// Converts the number of bytes read by this operation into its outcome.
+    fn outcome(&self, bytes_read: usize) -> Result<(), ReadError> {
+        if bytes_read == self.len {
+            Ok(())
+        } else if bytes_read == 0 {
+            Err(ReadError::Failed {
+                fault_address: self.remote_base,
+            })
+        } else {
+            Err(ReadError::Partial {
+                bytes_read,
+                fault_address: self.remote_base + bytes_read,
+            })
+        }
+    }
+}
+
+/// Allows to read memory from different locations in debuggee's memory as a single operation.
+/// On Linux, this will correspond to a single system call / context switch in the common case.
+/// Memory that can't be read this way (e.g. read-protected pages) is read through `/proc/pid/mem`
+/// or `PTRACE_PEEKDATA`, which are slower but ignore page protections.
+pub struct ReadMemory<'a> {
+    pid: Pid,
+    read_ops: Vec<ReadOp>,
+    _marker: PhantomData<&'a mut ()>,
+}
+
+impl<'a> ReadMemory<'a> {
+    pub(super) fn new(pid: Pid) -> Self {
+        ReadMemory {
+            pid,
+            read_ops: Vec::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Reads a value of type `T` from debuggee's memory at location `remote_base`.
+    /// This value will be written to the provided variable `val`.
+    /// You should call `apply` in order to execute the memory read operation.
+    /// The provided variable `val` can't be accessed until either `apply` is called or `self` is
+    /// dropped.
+    ///
+    /// # Safety
+    ///
+    /// The type `T` must not have any invalid values.
+    /// For example `T` must not be a `bool`, as `transmute::<u8, bool>(2)` is not a valid value for a bool.
+    /// In case of doubt, wrap the type in [`mem::MaybeUninit`].
+    ///
+    /// If the read operation fails or is partial (see [`ReadError`]), the bytes of `val` that
+    /// couldn't be read keep their previous contents, so `T` must be valid for any mix of old and
+    /// new bytes too.
+    pub unsafe fn read<T>(mut self, val: &'a mut T, remote_base: usize) -> Self {
+        self.read_ops.push(ReadOp {
+            remote_base,
+            len: mem::size_of::<T>(),
+            local_ptr: val as *mut T as *mut libc::c_void,
+        });
+
+        self
+    }
+
+    /// Reads `val.len()` bytes from debuggee's memory at location `remote_base` into `val`.
+    /// Unlike `read`, this is safe as bytes don't have any invalid values.
+    pub fn read_slice(mut self, val: &'a mut [u8], remote_base: usize) -> Self {
+        self.read_ops.push(ReadOp {
+            remote_base,
+            len: val.len(),
+            local_ptr: val.as_mut_ptr() as *mut libc::c_void,
+        });
+
+        self
+    }
+
+    /// Executes the memory read operation.
+    /// Returns the outcome of each read operation in the order they were added.
+    pub fn apply(self) -> Vec<Result<(), ReadError>> {
+        let mut results = Vec::with_capacity(self.read_ops.len());
+
+        // `process_vm_readv` stops at the first location it can't read from, so we read the rest
+        // of the failed operation in a slower way and continue with the next operations.
+        while results.len() < self.read_ops.len() {
+            let read_ops = &self.read_ops[results.len()..];
+            let mut bytes_read = self.read_vectored(read_ops);
+
+            for op in read_ops {
+                if bytes_read >= op.len {
+                    bytes_read -= op.len;
+                    results.push(Ok(()));
+                    continue;
+                }
+                let done = bytes_read + read_protected(self.pid, op, bytes_read);
+                results.push(op.outcome(done));
+                break;
+            }
+        }
+
+        results
+    }
+
+    /// Executes the memory read operation like `apply`, but fails if any of the read operations
+    /// has failed or has only been partially completed.
+    pub fn apply_all(self) -> Result<(), ReadError> {
+        self.apply().into_iter().collect()
+    }
+
+    /// Reads the given operations using a single `process_vm_readv` call.
+    /// Returns the number of bytes read.
+    fn read_vectored(&self, read_ops: &[ReadOp]) -> usize {
+        // Create a list of `IoVec`s and remote `IoVec`s
+        let remote_iov = read_ops
+            .iter()
+            .map(ReadOp::as_remote_iovec)
+            .collect::<Vec<_>>();
+
+        let local_iov = read_ops
+            .iter()
+            .map(ReadOp::as_local_iovec)
+            .collect::<Vec<_>>();
+
+        let bytes_read = unsafe {
+            // Safety: the local buffers are exclusively borrowed and valid for writes of their
+            // length for the lifetime `'a`, and the remote buffers are only accessed by the kernel.
+            libc::process_vm_readv(
+                self.pid.into(),
+                local_iov.as_ptr(),
+                local_iov.len() as libc::c_ulong,
+                remote_iov.as_ptr(),
+                remote_iov.len() as libc::c_ulong,
+                0,
+            )
+        };
+
+        // On failure nothing has been read. The exact error is not interesting, as the first
+        // operation is retried with `read_protected` which will report it.
+        if bytes_read == -1 {
+            0
+        } else {
+            bytes_read as usize
+        }
+    }
+}
+
+/// Reads the bytes of `op` starting at `offset` ignoring page protections.
+/// Returns the number of bytes read, which is smaller than requested if an inaccessible address
+/// has been reached.
+/// `/proc/pid/mem` is preferred as it allows to read an arbitrary number of bytes with a single
+/// system call, but it is not always available (e.g. when procfs is not mounted).
+fn read_protected(pid: Pid, op: &ReadOp, offset: usize) -> usize {
+    let buf = &mut op.buffer()[offset..];
+    let remote_base = op.remote_base + offset;
+
+    if let Ok(mem) = File::open(format!("/proc/{}/mem", pid)) {
+        let mut done = 0;
+        while done < buf.len() {
+            match mem.read_at(&mut buf[done..], (remote_base + done) as u64) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => done += n,
+            }
+        }
+        return done;
+    }
+
+    // `PTRACE_PEEKDATA` reads whole words, so we need to skip the surrounding bytes.
+    const WORD_SIZE: usize = mem::size_of::<libc::c_long>();
+    let mut done = 0;
+    let mut addr = remote_base - remote_base % WORD_SIZE;
+    while addr < remote_base + buf.len() {
+        let word = match ptrace::read(pid, addr as ptrace::AddressType) {
+            Ok(word) => word.to_ne_bytes(),
+            Err(_) => break,
+        };
+        for (i, byte) in word.iter().enumerate() {
+            let remote_addr = addr + i;
+            if remote_addr >= remote_base && remote_addr < remote_base + buf.len() {
+                buf[remote_addr - remote_base] = *byte;
+                done += 1;
+            }
+        }
+        addr += WORD_SIZE;
+    }
+    done
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ReadError, ReadMemory};
+    use nix::unistd::getpid;
+
+    use std::alloc::{alloc_zeroed, dealloc, Layout};
+
+    use nix::sys::mman::{mmap, mprotect, munmap, MapFlags, ProtFlags};
+
+    #[test]
+    fn read_memory() {
+        let var: usize = 52;
+        let var2: u8 = 128;
+
+        let mut read_var_op: usize = 0;
+        let mut read_var2_op: u8 = 0;
+
+        let res = unsafe {
+            ReadMemory::new(getpid())
+                .read(&mut read_var_op, &var as *const _ as usize)
+                .read(&mut read_var2_op, &var2 as *const _ as usize)
+                .apply()
+        };
+
+        assert_eq!(res, vec![Ok(()), Ok(())]);
+
+        assert_eq!(read_var2_op, var2);
+        assert_eq!(read_var_op, var);
+    }
+
+    const PAGE_SIZE: usize = 4096;
+
+    #[test]
+    fn read_protected_memory() {
+        let mut read_var_op: usize = 0;
+
+        unsafe {
+            let layout = Layout::from_size_align(PAGE_SIZE, PAGE_SIZE).unwrap();
+            let ptr = alloc_zeroed(layout);
+
+            *(ptr as *mut usize) = 9921;
+
+            mprotect(
+                ptr as *mut std::ffi::c_void,
+                PAGE_SIZE,
+                ProtFlags::PROT_NONE,
+            )
+            .expect("Failed to mprotect");
+
+            let res = ReadMemory::new(getpid())
+                .read(&mut read_var_op, ptr as *const _ as usize)
+                .apply();
+
+            assert_eq!(res, vec![Ok(())]);
+            assert_eq!(read_var_op, 9921);
+
+            mprotect(
+                ptr as *mut std::ffi::c_void,
+                PAGE_SIZE,
+                ProtFlags::PROT_WRITE,
+            )
+            .expect("Failed to mprotect");
+            dealloc(ptr, layout);
+        }
+    }
+
+    #[test]
+    fn read_cross_page_memory() {
+        let mut read_var_op = [0u32; 2];
+
+        unsafe {
+            let layout = Layout::from_size_align(PAGE_SIZE * 2, PAGE_SIZE).unwrap();
+            let ptr = alloc_zeroed(layout);
+
+            let array_ptr = (ptr as usize + PAGE_SIZE - std::mem::size_of::<u32>()) as *mut u8;
+            *(array_ptr as *mut [u32; 2]) = [123, 456];
+
+            let second_page_ptr = (ptr as usize + PAGE_SIZE) as *mut std::ffi::c_void;
+
+            mprotect(second_page_ptr, PAGE_SIZE, ProtFlags::PROT_NONE).expect("Failed to mprotect");
+
+            let res = ReadMemory::new(getpid())
+                .read(&mut read_var_op, array_ptr as *const _ as usize)
+                .apply();
+
+            assert_eq!(res, vec![Ok(())]);
+            assert_eq!([123, 456], read_var_op);
+
+            mprotect(second_page_ptr, PAGE_SIZE, ProtFlags::PROT_WRITE)
+                .expect("Failed to mprotect");
+            dealloc(ptr, layout);
+        }
+    }
+
+    #[test]
+    fn read_unmapped_memory() {
+        let mut read_var_op = [0u32; 2];
+        let mut read_var2_op: usize = 0;
+        let var2: usize = 52;
+
+        unsafe {
+            let ptr = mmap(
+                std::ptr::null_mut(),
+                PAGE_SIZE * 2,
+                ProtFlags::PROT_READ | ProtFlags::PROT_WRITE,
+                MapFlags::MAP_PRIVATE | MapFlags::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+            .expect("Failed to mmap");
+
+            let array_ptr = (ptr as usize + PAGE_SIZE - std::mem::size_of::<u32>()) as *mut u8;
+            *(array_ptr as *mut [u32; 2]) = [123, 456];
+
+            let second_page_ptr = (ptr as usize + PAGE_SIZE) as *mut std::ffi::c_void;
+            munmap(second_page_ptr, PAGE_SIZE).expect("Failed to munmap");
+
+            let res = ReadMemory::new(getpid())
+                .read(&mut read_var_op, array_ptr as *const _ as usize)
+                .read(&mut read_var2_op, &var2 as *const _ as usize)
+                .apply();
+
+            // The first value is read partially, but the following reads still succeed.
+            assert_eq!(
+                res,
+                vec![
+                    Err(ReadError::Partial {
+                        bytes_read: 4,
+                        fault_address: second_page_ptr as usize,
+                    }),
+                    Ok(()),
+                ]
+            );
+            assert_eq!([123, 0], read_var_op);
+            assert_eq!(read_var2_op, var2);
+
+            // The first error is reported when all operations are required to succeed.
+            let res = ReadMemory::new(getpid())
+                .read(&mut read_var2_op, &var2 as *const _ as usize)
+                .read(&mut read_var_op, array_ptr as *const _ as usize)
+                .apply_all();
+
+            assert_eq!(
+                res,
+                Err(ReadError::Partial {
+                    bytes_read: 4,
+                    fault_address: second_page_ptr as usize,
+                })
+            );
+
+            let res = ReadMemory::new(getpid())
+                .read(&mut read_var2_op, second_page_ptr as usize)
+                .apply();
+
+            assert_eq!(
+                res,
+                vec![Err(ReadError::Failed {
+                    fault_address: second_page_ptr as usize,
+                })]
+            );
+
+            munmap(ptr, PAGE_SIZE).expect("Failed to munmap");
+        }
+    }
+}
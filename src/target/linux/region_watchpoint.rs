@@ -0,0 +1,244 @@
+use nix::{
+    sys::{
+        ptrace,
+        signal::Signal,
+        wait::{waitpid, WaitPidFlag, WaitStatus},
+    },
+    unistd::Pid,
+};
+use std::ops::Range;
+
+use super::{DebugEvent, LinuxTarget, WatchpointError, WatchpointMode};
+
+/// The `si_code` of a `SIGSEGV` caused by an access which the page protection doesn't allow.
+const SEGV_ACCERR: libc::c_int = 2;
+
+/// A watchpoint for a region of memory of any size, implemented by revoking the access to the
+/// pages containing it with `mprotect`.
+///
+/// Accesses by the kernel, e.g. when a buffer is passed to `read`, fail with `EFAULT` instead of
+/// being reported, and other threads can access the region unnoticed while an access is executed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegionWatchpoint {
+    range: Range<usize>,
+    mode: WatchpointMode,
+    // The pages containing the region along with their original protection flags.
+    pages: Vec<(Range<usize>, libc::c_int)>,
+}
+
+impl RegionWatchpoint {
+    /// Returns the watched memory.
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    /// Returns the kind of accesses the watchpoint is triggered by.
+    pub fn mode(&self) -> WatchpointMode {
+        self.mode
+    }
+
+    /// Returns the protection flags of pages while they are watched.
+    fn watched_protection(&self, original: libc::c_int) -> libc::c_int {
+        match self.mode {
+            WatchpointMode::Write => original & !libc::PROT_WRITE,
+            _ => libc::PROT_NONE,
+        }
+    }
+
+    fn contains_page(&self, addr: usize) -> bool {
+        self.pages.iter().any(|(pages, _)| pages.contains(&addr))
+    }
+
+    fn shares_pages(&self, other: &Range<usize>) -> bool {
+        self.pages
+            .iter()
+            .any(|(pages, _)| pages.start < other.end && other.start < pages.end)
+    }
+}
+
+impl LinuxTarget {
+    /// Returns a list of all set region watchpoints.
+    pub fn region_watchpoints(&self) -> &[RegionWatchpoint] {
+        &self.region_watchpoints
+    }
+
+    /// Sets a watchpoint for the memory in `range`, which can be larger than the hardware
+    /// watchpoints allow. Accesses are reported as `DebugEvent::RegionAccess` after they have
+    /// been executed. Only write and read/write accesses can be watched, and regions can't
+    /// share pages. Accesses are matched by their first byte, so an access which starts below
+    /// the region and overlaps it isn't reported.
+    pub fn set_region_watchpoint(
+        &mut self,
+        range: Range<usize>,
+        mode: WatchpointMode,
+    ) -> Result<(), WatchpointError> {
+        if range.start >= range.end || mode == WatchpointMode::Execute {
+            return Err(WatchpointError::InvalidRange {
+                addr: range.start,
+                size: range.end.saturating_sub(range.start),
+            });
+        }
+        let page_size = page_size();
+        let page_range = align_down(range.start, page_size)..align_up(range.end, page_size);
+        if let Some(watchpoint) = self
+            .region_watchpoints
+            .iter()
+            .find(|watchpoint| watchpoint.shares_pages(&page_range))
+        {
+            return Err(WatchpointError::AlreadySet(watchpoint.range.start));
+        }
+
+        let maps = self.memory_maps().map_err(WatchpointError::Debuggee)?;
+        let mut pages = Vec::new();
+        let mut next = page_range.start;
+        for map in maps {
+            if map.range.end <= next || map.range.start >= page_range.end {
+                continue;
+            }
+            if map.range.start > next {
+                break;
+            }
+            let end = map.range.end.min(page_range.end);
+            let mut protection = libc::PROT_NONE;
+            if map.is_read {
+                protection |= libc::PROT_READ;
+            }
+            if map.is_write {
+                protection |= libc::PROT_WRITE;
+            }
+            if map.is_exec {
+                protection |= libc::PROT_EXEC;
+            }
+            pages.push((next..end, protection));
+            next = end;
+        }
+        if next != page_range.end {
+            return Err(WatchpointError::Debuggee(
+                format!("The memory at 0x{:x} is not mapped", next).into(),
+            ));
+        }
+
+        let watchpoint = RegionWatchpoint { range, mode, pages };
+        self.protect(self.event_thread, &watchpoint, true)
+            .map_err(WatchpointError::Debuggee)?;
+        self.region_watchpoints.push(watchpoint);
+        Ok(())
+    }
+
+    /// Removes the region watchpoint starting at `addr`, restoring the protection of its pages.
+    pub fn remove_region_watchpoint(&mut self, addr: usize) -> Result<(), WatchpointError> {
+        let index = self
+            .region_watchpoints
+            .iter()
+            .position(|watchpoint| watchpoint.range.start == addr)
+            .ok_or(WatchpointError::NotFound(addr))?;
+        self.protect(self.event_thread, &self.region_watchpoints[index], false)
+            .map_err(WatchpointError::Debuggee)?;
+        self.region_watchpoints.remove(index);
+        Ok(())
+    }
+
+    /// Handles a `SIGSEGV` which could have been caused by a region watchpoint.
+    /// Accesses to watched pages are executed with their original protection. Returns `None` if
+    /// the access has been outside of the watched region and the thread has been resumed, or if
+    /// the thread has reported another status which has been deferred.
+    pub(super) fn region_watchpoint_event(
+        &mut self,
+        thread: Pid,
+    ) -> Result<Option<DebugEvent>, Box<dyn std::error::Error>> {
+        let siginfo = ptrace::getsiginfo(thread)?;
+        let addr = unsafe { siginfo.si_addr() } as usize;
+        let index = match self
+            .region_watchpoints
+            .iter()
+            .position(|watchpoint| watchpoint.contains_page(addr))
+        {
+            Some(index) if siginfo.si_code == SEGV_ACCERR => index,
+            // Not caused by us, e.g. a null pointer dereference.
+            _ => {
+                self.pending_signal = Some(Signal::SIGSEGV);
+                return Ok(Some(DebugEvent::Signal {
+                    signal: Signal::SIGSEGV,
+                    thread,
+                }));
+            }
+        };
+
+        let watchpoint = &self.region_watchpoints[index];
+        self.protect(thread, watchpoint, false)?;
+        ptrace::step(thread, None)?;
+        match waitpid(thread, Some(WaitPidFlag::__WALL))? {
+            WaitStatus::Stopped(_, Signal::SIGTRAP) => self.protect(thread, watchpoint, true)?,
+            // The pages can't be protected again once the thread has exited.
+            status @ WaitStatus::Exited(..) | status @ WaitStatus::Signaled(..) => {
+                self.deferred_statuses.push(status);
+                return Ok(None);
+            }
+            // The access hasn't been executed, e.g. because a signal has arrived first. It faults
+            // again once the thread is resumed after the status has been reported.
+            status => {
+                self.protect(thread, watchpoint, true)?;
+                self.deferred_statuses.push(status);
+                return Ok(None);
+            }
+        }
+
+        // Only the first byte of an access is known, so accesses starting below the region are
+        // continued even if they overlap it.
+        if watchpoint.range.contains(&addr) {
+            Ok(Some(DebugEvent::RegionAccess {
+                region: watchpoint.range.start,
+                addr,
+                thread,
+            }))
+        } else {
+            ptrace::cont(thread, None)?;
+            Ok(None)
+        }
+    }
+
+    /// Revokes or restores the access to the pages of a region watchpoint, using a stopped thread
+    /// to call `mprotect`.
+    fn protect(
+        &self,
+        thread: Pid,
+        watchpoint: &RegionWatchpoint,
+        watched: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        for (pages, original) in &watchpoint.pages {
+            let protection = if watched {
+                watchpoint.watched_protection(*original)
+            } else {
+                *original
+            };
+            let res = self.thread_syscall(
+                thread,
+                libc::SYS_mprotect as _,
+                [
+                    pages.start as _,
+                    (pages.end - pages.start) as _,
+                    protection as _,
+                    0,
+                    0,
+                    0,
+                ],
+            )? as i64;
+            if res < 0 {
+                return Err(nix::Error::from_errno(nix::errno::from_i32(-res as i32)).into());
+            }
+        }
+        Ok(())
+    }
+}
+
+pub(super) fn page_size() -> usize {
+    unsafe { libc::sysconf(libc::_SC_PAGESIZE) as usize }
+}
+
+fn align_down(addr: usize, alignment: usize) -> usize {
+    addr - addr % alignment
+}
+
+fn align_up(addr: usize, alignment: usize) -> usize {
+    align_down(addr + alignment - 1, alignment)
+}
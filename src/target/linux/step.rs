@@ -0,0 +1,319 @@
+use nix::{sys::ptrace, unistd::Pid};
+
+use super::{DebugEvent, Frame, LinuxTarget};
+use crate::{
+    symbol::Dwarf,
+    target::{ReadError, UnixTarget},
+};
+
+/// The maximum length of an x86_64 instruction.
+const MAX_INSTRUCTION_LEN: usize = 15;
+
+impl LinuxTarget {
+    /// Executes a single instruction of the thread which has reported the last debug event.
+    /// Returns `DebugEvent::SingleStep` once the instruction has been executed, or another event
+    /// if it has occurred first.
+    pub fn step(&mut self) -> Result<DebugEvent, Box<dyn std::error::Error>> {
+        self.step_thread(self.event_thread)
+    }
+
+    /// Executes a single instruction of `thread`, which has to be stopped, e.g. after attaching.
+    /// Pending signals are only delivered to the thread which has reported the last debug event.
+    pub fn step_thread(&mut self, thread: Pid) -> Result<DebugEvent, Box<dyn std::error::Error>> {
+        if self.step_over_breakpoint(thread)? {
+            self.event_thread = thread;
+            return Ok(DebugEvent::SingleStep { thread });
+        }
+        if self.has_deferred_status(thread) {
+            return self.next_event();
+        }
+        let signal = if thread == self.event_thread {
+            self.pending_signal
+        } else {
+            None
+        };
+        ptrace::step(thread, signal)?;
+        self.next_event()
+    }
+
+    /// Executes a single instruction like `step`, but executes a `call` instruction along with
+    /// the whole function it calls.
+    pub fn step_over(&mut self) -> Result<DebugEvent, Box<dyn std::error::Error>> {
+        let regs = ptrace::getregs(self.event_thread)?;
+        let code = self.read_code(regs.rip as usize)?;
+        match call_instruction_len(&code) {
+            // The stack pointer is back to its current value once the call returns.
+            Some(len) => self.run_to(regs.rip as usize + len, regs.rsp),
+            None => self.step(),
+        }
+    }
+
+    /// Continues execution until the current function returns to its caller.
+    ///
+    /// The return address is found by unwinding the stack with `unwind`, which uses the call frame
+    /// information of `debuginfo` and falls back to the frame pointer chain without it.
+    pub fn step_out(
+        &mut self,
+        debuginfo: &Dwarf,
+    ) -> Result<DebugEvent, Box<dyn std::error::Error>> {
+        let frames = self.unwind(debuginfo)?;
+        let (cfa, return_address) = match frames.as_slice() {
+            [Frame { cfa: Some(cfa), .. }, caller, ..] => (*cfa, caller.pc),
+            _ => return Err("Failed to find the return address of the current function".into()),
+        };
+
+        // The return address is popped from the stack on return, so the stack pointer is back at
+        // the canonical frame address.
+        self.run_to(return_address, cfa as u64)
+    }
+
+    /// Continues execution until a statement of another source line is reached, stepping into
+    /// functions that are called in between. Calls of functions without line information (e.g.
+    /// from libraries without debug info) are stepped over.
+    pub fn step_line(
+        &mut self,
+        debuginfo: &Dwarf,
+    ) -> Result<DebugEvent, Box<dyn std::error::Error>> {
+        self.step_source_line(debuginfo, true)
+    }
+
+    /// Continues execution until a statement of another source line is reached, stepping over
+    /// functions that are called in between.
+    pub fn next_line(
+        &mut self,
+        debuginfo: &Dwarf,
+    ) -> Result<DebugEvent, Box<dyn std::error::Error>> {
+        self.step_source_line(debuginfo, false)
+    }
+
+    fn step_source_line(
+        &mut self,
+        debuginfo: &Dwarf,
+        step_into: bool,
+    ) -> Result<DebugEvent, Box<dyn std::error::Error>> {
+        let thread = self.event_thread;
+        let regs = ptrace::getregs(thread)?;
+        let start_line = debuginfo
+            .get_line_row(regs.rip as usize)
+            .map(|row| (row.file, row.line));
+
+        loop {
+            let regs = ptrace::getregs(thread)?;
+            let code = self.read_code(regs.rip as usize)?;
+            let event = match call_instruction_len(&code) {
+                Some(len) if step_into => {
+                    let event = self.step()?;
+                    let pc = ptrace::getregs(thread)?.rip as usize;
+                    if event == (DebugEvent::SingleStep { thread })
+                        && debuginfo.get_line_row(pc).is_none()
+                    {
+                        // There is nothing to step through in the called function.
+                        self.run_to(regs.rip as usize + len, regs.rsp)?
+                    } else {
+                        event
+                    }
+                }
+                Some(len) => self.run_to(regs.rip as usize + len, regs.rsp)?,
+                None => self.step()?,
+            };
+            if event != (DebugEvent::SingleStep { thread }) {
+                return Ok(event);
+            }
+
+            // Stop at the start of a statement only, as the code of a line can be interleaved
+            // with the code of other lines and a line can be split into several statements.
+            let pc = ptrace::getregs(thread)?.rip as usize;
+            if let Some(row) = debuginfo.get_line_row(pc) {
+                if row.address == pc
+                    && row.is_stmt
+                    && row.line != 0
+                    && Some((row.file, row.line)) != start_line
+                {
+                    return Ok(event);
+                }
+            }
+        }
+    }
+
+    /// Continues execution of the event thread until it reaches `addr` with the stack pointer
+    /// being at least `min_sp`, which allows to skip recursive calls of the current function.
+    /// A temporary breakpoint is set at `addr` for the duration of this call if required.
+    pub(super) fn run_to(
+        &mut self,
+        addr: usize,
+        min_sp: u64,
+    ) -> Result<DebugEvent, Box<dyn std::error::Error>> {
+        let thread = self.event_thread;
+
+        // `Some(enabled)` if there is a user breakpoint at `addr`.
+        let user_breakpoint = self
+            .breakpoints
+            .find_by_addr(addr)
+            .map(|breakpoint| breakpoint.is_enabled());
+        match user_breakpoint {
+            None => self.breakpoints.set(&[addr]),
+            Some(false) => self.breakpoints.enable(&[addr]),
+            Some(true) => Ok(()),
+        }
+        .map_err(|mut errors| errors.remove(0))?;
+
+        let res = loop {
+            if let Err(err) = self.unpause() {
+                break Err(err);
+            }
+            let event = match self.next_event() {
+                Ok(event) => event,
+                Err(err) => break Err(err),
+            };
+            match event {
+                DebugEvent::BreakpointHit {
+                    addr: hit_addr,
+                    thread: hit_thread,
+                } if hit_addr == addr => {
+                    if hit_thread == thread
+                        && ptrace::getregs(thread).map(|regs| regs.rsp >= min_sp)?
+                    {
+                        break Ok(DebugEvent::SingleStep { thread });
+                    }
+                    if user_breakpoint == Some(true) {
+                        break Ok(event);
+                    }
+                    // The temporary breakpoint has been hit by another thread or by a recursive
+                    // call, so it's not reported.
+                }
+                event => break Ok(event),
+            }
+        };
+
+        let cleanup = match user_breakpoint {
+            None => self.breakpoints.remove(&[addr]),
+            Some(false) => self.breakpoints.disable(&[addr]),
+            Some(true) => Ok(()),
+        };
+        // The breakpoints are gone if the debuggee has exited or has executed a new program.
+        if let Ok(DebugEvent::Exited(_)) | Ok(DebugEvent::Killed(_)) | Ok(DebugEvent::Exec) = res {
+            return res;
+        }
+        cleanup.map_err(|mut errors| errors.remove(0))?;
+        res
+    }
+
+    /// Reads the code at `addr` as it would be without software breakpoints.
+    fn read_code(
+        &self,
+        addr: usize,
+    ) -> Result<[u8; MAX_INSTRUCTION_LEN], Box<dyn std::error::Error>> {
+        let mut code = [0u8; MAX_INSTRUCTION_LEN];
+        // The code can be followed by an unmapped page, so partial reads are fine.
+        let res = unsafe { self.read().read(&mut code, addr).apply_all() };
+        if let Err(err @ ReadError::Failed { .. }) = res {
+            return Err(Box::new(err));
+        }
+
+        for breakpoint in self.breakpoints.get_all() {
+            if breakpoint.is_enabled()
+                && breakpoint.addr() >= addr
+                && breakpoint.addr() < addr + MAX_INSTRUCTION_LEN
+            {
+                code[breakpoint.addr() - addr] = breakpoint.original_byte();
+            }
+        }
+        Ok(code)
+    }
+}
+
+/// Returns the length of the instruction at the start of `code` if it is a `call` instruction.
+fn call_instruction_len(code: &[u8]) -> Option<usize> {
+    let mut len = 0;
+
+    // Skip legacy prefixes.
+    while let Some(0x26 | 0x2e | 0x36 | 0x3e | 0x64 | 0x65 | 0x66 | 0x67 | 0xf0 | 0xf2 | 0xf3) =
+        code.get(len)
+    {
+        len += 1;
+    }
+    // Skip the REX prefix.
+    if let Some(0x40..=0x4f) = code.get(len) {
+        len += 1;
+    }
+
+    match code.get(len)? {
+        // call rel32
+        0xe8 => Some(len + 5),
+        // call r/m64 (`/2`) and far call m16:64 (`/3`)
+        0xff => {
+            let modrm = *code.get(len + 1)?;
+            let reg = (modrm >> 3) & 0b111;
+            if reg != 2 && reg != 3 {
+                return None;
+            }
+            let sib = code.get(len + 2).copied().unwrap_or(0);
+            Some(len + 2 + modrm_operand_len(modrm, sib))
+        }
+        _ => None,
+    }
+}
+
+/// Returns the number of bytes that follow a ModR/M byte (SIB byte and displacement).
+fn modrm_operand_len(modrm: u8, sib: u8) -> usize {
+    let mode = modrm >> 6;
+    let rm = modrm & 0b111;
+    if mode == 0b11 {
+        // Register operand.
+        return 0;
+    }
+
+    let sib_len = if rm == 0b100 { 1 } else { 0 };
+    let disp_len = match mode {
+        0b00 if rm == 0b101 => 4,                         // rip-relative
+        0b00 if rm == 0b100 && sib & 0b111 == 0b101 => 4, // SIB without a base register
+        0b00 => 0,
+        0b01 => 1,
+        _ => 4,
+    };
+    sib_len + disp_len
+}
+
+#[cfg(test)]
+mod tests {
+    use super::call_instruction_len;
+
+    #[test]
+    fn call_instructions() {
+        // call rel32
+        assert_eq!(
+            call_instruction_len(&[0xe8, 0x10, 0x00, 0x00, 0x00]),
+            Some(5)
+        );
+        // call rax
+        assert_eq!(call_instruction_len(&[0xff, 0xd0, 0x00]), Some(2));
+        // call r11
+        assert_eq!(call_instruction_len(&[0x41, 0xff, 0xd3, 0x00]), Some(3));
+        // call qword ptr [rip + 0x2fe2]
+        assert_eq!(
+            call_instruction_len(&[0xff, 0x15, 0xe2, 0x2f, 0x00, 0x00]),
+            Some(6)
+        );
+        // call qword ptr [rax + 0x18]
+        assert_eq!(call_instruction_len(&[0xff, 0x50, 0x18]), Some(3));
+        // call qword ptr [rsp + 0x8]
+        assert_eq!(call_instruction_len(&[0xff, 0x54, 0x24, 0x08]), Some(4));
+        // call qword ptr [rbx*8 + 0x601000]
+        assert_eq!(
+            call_instruction_len(&[0xff, 0x14, 0xdd, 0x00, 0x10, 0x60, 0x00]),
+            Some(7)
+        );
+    }
+
+    #[test]
+    fn non_call_instructions() {
+        // jmp rax
+        assert_eq!(call_instruction_len(&[0xff, 0xe0, 0x00]), None);
+        // push rbp
+        assert_eq!(call_instruction_len(&[0x55, 0x00, 0x00]), None);
+        // ret
+        assert_eq!(call_instruction_len(&[0xc3, 0x00, 0x00]), None);
+        // inc dword ptr [rax]
+        assert_eq!(call_instruction_len(&[0xff, 0x00, 0x00]), None);
+    }
+}
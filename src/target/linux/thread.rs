@@ -0,0 +1,191 @@
+use nix::{
+    errno::Errno,
+    sys::{
+        ptrace,
+        signal::Signal,
+        wait::{waitpid, WaitPidFlag, WaitStatus},
+    },
+    unistd::Pid,
+};
+
+use super::LinuxTarget;
+
+/// A thread of a debuggee, see [`LinuxTarget::threads`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Thread {
+    pub tid: Pid,
+    /// The name of the thread as listed in `/proc/<pid>/task/<tid>/comm`, which is truncated to
+    /// 15 bytes.
+    pub name: String,
+    pub state: ThreadState,
+}
+
+/// The scheduling state of a thread, as listed in `/proc/<pid>/task/<tid>/stat`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThreadState {
+    Running,
+    Sleeping,
+    /// Waiting in an uninterruptible sleep, usually for IO.
+    DiskSleep,
+    /// Stopped by a signal, e.g. `SIGSTOP`.
+    Stopped,
+    /// Stopped by the debugger, e.g. because it has reported a debug event.
+    TracingStop,
+    Zombie,
+    Dead,
+    /// A state which is specific to some kernel versions.
+    Other(char),
+}
+
+impl ThreadState {
+    fn from_char(state: char) -> ThreadState {
+        match state {
+            'R' => ThreadState::Running,
+            'S' => ThreadState::Sleeping,
+            'D' => ThreadState::DiskSleep,
+            'T' => ThreadState::Stopped,
+            't' => ThreadState::TracingStop,
+            'Z' => ThreadState::Zombie,
+            'X' | 'x' => ThreadState::Dead,
+            state => ThreadState::Other(state),
+        }
+    }
+}
+
+impl LinuxTarget {
+    /// Returns the threads of the debuggee which are traced by us, starting with the main thread.
+    pub fn threads(&self) -> Result<Vec<Thread>, Box<dyn std::error::Error>> {
+        self.threads
+            .iter()
+            .map(|&tid| {
+                let task = format!("/proc/{}/task/{}", self.pid, tid);
+                let name = std::fs::read_to_string(format!("{}/comm", task))?;
+                let stat = std::fs::read_to_string(format!("{}/stat", task))?;
+                Ok(Thread {
+                    tid,
+                    name: name.trim_end_matches('\n').to_string(),
+                    state: parse_state(&stat)
+                        .ok_or_else(|| format!("Invalid thread status: {}", stat))?,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the state of a thread, or `None` if it doesn't exist anymore.
+    pub(super) fn thread_state(&self, tid: Pid) -> Option<ThreadState> {
+        let stat = std::fs::read_to_string(format!("/proc/{}/task/{}/stat", self.pid, tid)).ok()?;
+        parse_state(&stat)
+    }
+
+    /// Attaches to the threads of an attached debuggee other than the main one and lets them run.
+    /// Threads created afterwards are traced automatically.
+    pub(super) fn attach_threads(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        // Threads can be created while we are attaching, so repeat until no new ones are found.
+        loop {
+            let mut attached = false;
+            for entry in std::fs::read_dir(format!("/proc/{}/task", self.pid))? {
+                let tid = match entry?.file_name().to_str().and_then(|tid| tid.parse().ok()) {
+                    Some(tid) => Pid::from_raw(tid),
+                    None => continue,
+                };
+                if self.threads.contains(&tid) {
+                    continue;
+                }
+
+                // The thread could have exited in the meantime.
+                if ptrace::attach(tid).is_err() {
+                    continue;
+                }
+                waitpid(tid, Some(WaitPidFlag::__WALL))?;
+                self.set_ptrace_options(tid)?;
+                if !self.watchpoints.is_empty() {
+                    self.set_debug_registers(tid)?;
+                }
+                self.threads.push(tid);
+                ptrace::cont(tid, None)?;
+                attached = true;
+            }
+            if !attached {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Calls `f` for each thread of the debuggee while it is stopped, e.g. to modify its
+    /// registers. Threads which are running are stopped and resumed afterwards.
+    pub(super) fn with_stopped_threads(
+        &mut self,
+        mut f: impl FnMut(&Self, Pid) -> Result<(), Box<dyn std::error::Error>>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        for thread in self.threads.clone() {
+            let running = thread != self.event_thread && !self.has_deferred_status(thread);
+            if running && !self.stop_thread(thread)? {
+                continue;
+            }
+            let res = f(self, thread);
+            if running && !self.has_deferred_status(thread) {
+                ptrace::cont(thread, None)?;
+            }
+            res?;
+        }
+        Ok(())
+    }
+
+    /// Stops a running thread with `SIGSTOP`.
+    /// If the thread reports another status first, the status is reported by `next_event` later
+    /// on, and the `SIGSTOP` is suppressed once it arrives.
+    /// Returns `false` if the thread has exited instead.
+    pub(super) fn stop_thread(&mut self, thread: Pid) -> Result<bool, Box<dyn std::error::Error>> {
+        let res = unsafe {
+            libc::syscall(
+                libc::SYS_tgkill,
+                self.pid.as_raw(),
+                thread.as_raw(),
+                libc::SIGSTOP,
+            )
+        };
+        Errno::result(res)?;
+
+        let status = waitpid(thread, Some(WaitPidFlag::__WALL))?;
+        if let WaitStatus::Stopped(_, Signal::SIGSTOP) = status {
+            return Ok(true);
+        }
+        let exited = matches!(status, WaitStatus::Exited(..) | WaitStatus::Signaled(..));
+        if !exited {
+            self.stopping_threads.push(thread);
+        }
+        self.deferred_statuses.push(status);
+        Ok(!exited)
+    }
+
+    pub(super) fn has_deferred_status(&self, thread: Pid) -> bool {
+        self.deferred_statuses
+            .iter()
+            .any(|status| status.pid() == Some(thread))
+    }
+}
+
+// The state follows the name of the thread, which is enclosed in parentheses and can contain
+// spaces and parentheses itself, e.g. `1234 (my (thread)) S 1 ...`.
+fn parse_state(stat: &str) -> Option<ThreadState> {
+    let (_, rest) = stat.rsplit_once(')')?;
+    rest.trim_start().chars().next().map(ThreadState::from_char)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_state, ThreadState};
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            parse_state("1234 (worker) S 1 1234 1234 0 -1"),
+            Some(ThreadState::Sleeping)
+        );
+        assert_eq!(
+            parse_state("1234 (a) (b)) t 1 1234 1234 0 -1"),
+            Some(ThreadState::TracingStop)
+        );
+        assert_eq!(parse_state("1234 (worker"), None);
+    }
+}
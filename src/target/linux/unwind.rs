@@ -0,0 +1,197 @@
+use nix::sys::ptrace;
+
+use super::{
+    memory_map::{self, find_map, MemoryMap},
+    LinuxTarget,
+};
+use crate::symbol::{Dwarf, InlineFrame, UnwindRegisters, RETURN_ADDRESS, RSP, UNWIND_REGISTERS};
+
+/// The DWARF register number of `rbp`.
+const RBP: usize = 6;
+
+/// The maximum number of frames returned by `LinuxTarget::unwind`, which guards against cycles
+/// in corrupted stacks.
+const MAX_FRAMES: usize = 1024;
+
+/// A frame of the call stack of a thread.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    /// The address of the instruction being executed, which is the return address for all
+    /// frames except the innermost one.
+    pub pc: usize,
+    /// The canonical frame address, i.e. the value of the stack pointer before the call
+    /// instruction which has created this frame. `None` if there is no call frame information
+    /// for `pc`.
+    pub cfa: Option<usize>,
+    /// The values of the registers in this frame, indexed by DWARF register number.
+    /// Registers whose values couldn't be recovered are `None`.
+    pub regs: UnwindRegisters,
+    /// The load bias of the module whose debug info has been used for unwinding, see
+    /// `LinuxTarget::load_bias`. Zero if the frame has been unwound without debug info.
+    pub load_bias: usize,
+}
+
+impl Frame {
+    /// Returns the address in the debug info which should be used to look up the function and
+    /// the source location of this frame, i.e. `pc` without the load bias. For caller frames this
+    /// is the address of the call instruction rather than the return address, which could
+    /// already belong to the next line or even another function if the call is the last
+    /// instruction of a function.
+    pub fn lookup_addr(&self, innermost: bool) -> usize {
+        let pc = self.pc.wrapping_sub(self.load_bias);
+        if innermost {
+            pc
+        } else {
+            pc.saturating_sub(1)
+        }
+    }
+
+    /// Returns the functions executing in this frame, innermost inlined function first, see
+    /// `Dwarf::get_inline_frames`.
+    pub fn symbolicate(
+        &self,
+        debuginfo: &Dwarf,
+        innermost: bool,
+    ) -> Result<Vec<InlineFrame>, Box<dyn std::error::Error>> {
+        debuginfo.get_inline_frames(self.lookup_addr(innermost))
+    }
+}
+
+impl LinuxTarget {
+    /// Unwinds the call stack of the thread which has reported the last debug event, innermost
+    /// frame first.
+    ///
+    /// Frames are unwound using the call frame information in `.eh_frame` and `.debug_frame`.
+    /// Frames without call frame information, e.g. in shared libraries or hand-written assembly,
+    /// are unwound by following the frame pointer chain instead, see `unwind_frame_pointers`.
+    /// Unwinding stops at the outermost frame, whose return address is undefined, or at the first
+    /// frame which doesn't look valid.
+    pub fn unwind(&self, debuginfo: &Dwarf) -> Result<Vec<Frame>, Box<dyn std::error::Error>> {
+        self.unwind_stack(Some(debuginfo))
+    }
+
+    /// Unwinds the call stack of the thread which has reported the last debug event by following
+    /// the chain of frame pointers saved on the stack, innermost frame first.
+    ///
+    /// This doesn't require any debug info, but all functions on the stack have to maintain `rbp`
+    /// as a frame pointer (e.g. when compiled with `-C force-frame-pointers=yes`). The caller of
+    /// the innermost function is skipped if it is stopped in a function prologue or epilogue.
+    /// Only the registers `rsp`, `rbp` and the return address are recovered.
+    pub fn unwind_frame_pointers(&self) -> Result<Vec<Frame>, Box<dyn std::error::Error>> {
+        self.unwind_stack(None)
+    }
+
+    fn unwind_stack(
+        &self,
+        debuginfo: Option<&Dwarf>,
+    ) -> Result<Vec<Frame>, Box<dyn std::error::Error>> {
+        let regs = ptrace::getregs(self.event_thread)?;
+        let maps = self.memory_maps()?;
+        // The call frame information is looked up by the addresses of the debug info.
+        let load_bias = match debuginfo {
+            Some(debuginfo) => memory_map::load_bias(&maps, debuginfo)?,
+            None => 0,
+        };
+        let mut frame = Frame {
+            pc: regs.rip as usize,
+            cfa: None,
+            regs: dwarf_registers(&regs),
+            load_bias,
+        };
+
+        let mut frames = Vec::new();
+        let mut read_memory = |addr: usize| -> Result<u64, Box<dyn std::error::Error>> {
+            let mut value = 0u64;
+            unsafe {
+                self.read().read(&mut value, addr).apply_all()?;
+            }
+            Ok(value)
+        };
+
+        while frames.len() < MAX_FRAMES {
+            let unwound = match debuginfo {
+                Some(debuginfo) => debuginfo.unwind_frame(
+                    frame.lookup_addr(frames.is_empty()),
+                    &frame.regs,
+                    &mut read_memory,
+                )?,
+                None => None,
+            };
+            let (cfa, caller_regs) = match unwound {
+                Some(unwound) => unwound,
+                None => match unwind_frame_pointer(&frame.regs, &maps, &mut read_memory)? {
+                    Some(unwound) => unwound,
+                    None => break,
+                },
+            };
+            frame.cfa = Some(cfa);
+            frames.push(frame.clone());
+
+            // Garbage return addresses don't point to code.
+            let pc = match caller_regs[RETURN_ADDRESS] {
+                Some(pc) if find_map(&maps, pc as usize).is_some_and(|map| map.is_exec) => {
+                    pc as usize
+                }
+                _ => return Ok(frames),
+            };
+            // The stack grows down, so the frame of the caller has to be above this one.
+            match (caller_regs[RSP], frame.regs[RSP]) {
+                (Some(caller_sp), Some(sp)) if caller_sp > sp => {}
+                _ => return Ok(frames),
+            }
+            frame = Frame {
+                pc,
+                cfa: None,
+                regs: caller_regs,
+                load_bias,
+            };
+        }
+
+        if frames.len() < MAX_FRAMES {
+            frames.push(frame);
+        }
+        Ok(frames)
+    }
+}
+
+/// Unwinds one stack frame using the frame pointer. Returns `None` if the frame pointer doesn't
+/// point to a valid frame record, i.e. the saved `rbp` and the return address.
+fn unwind_frame_pointer(
+    regs: &UnwindRegisters,
+    maps: &[MemoryMap],
+    read_memory: &mut dyn FnMut(usize) -> Result<u64, Box<dyn std::error::Error>>,
+) -> Result<Option<(usize, UnwindRegisters)>, Box<dyn std::error::Error>> {
+    let (rbp, rsp) = match (regs[RBP], regs[RSP]) {
+        (Some(rbp), Some(rsp)) => (rbp as usize, rsp as usize),
+        _ => return Ok(None),
+    };
+    // The frame record is pushed onto the stack of the current frame, which is 8-byte aligned.
+    if rbp % 8 != 0 || rbp < rsp {
+        return Ok(None);
+    }
+    match find_map(maps, rbp) {
+        Some(map) if map.is_read && map.is_write && rbp + 16 <= map.range.end => {}
+        _ => return Ok(None),
+    }
+
+    // The return address is pushed by the call instruction and is followed by the saved `rbp`.
+    let cfa = rbp + 16;
+    let mut caller_regs = [None; UNWIND_REGISTERS];
+    caller_regs[RBP] = Some(read_memory(rbp)?);
+    caller_regs[RETURN_ADDRESS] = Some(read_memory(rbp + 8)?);
+    caller_regs[RSP] = Some(cfa as u64);
+    Ok(Some((cfa, caller_regs)))
+}
+
+/// Converts the registers of a thread to the DWARF register numbering used for unwinding.
+fn dwarf_registers(regs: &libc::user_regs_struct) -> UnwindRegisters {
+    let values = [
+        regs.rax, regs.rdx, regs.rcx, regs.rbx, regs.rsi, regs.rdi, regs.rbp, regs.rsp, regs.r8,
+        regs.r9, regs.r10, regs.r11, regs.r12, regs.r13, regs.r14, regs.r15, regs.rip,
+    ];
+    let mut dwarf_regs = [None; UNWIND_REGISTERS];
+    for (dwarf_reg, value) in dwarf_regs.iter_mut().zip(values.iter()) {
+        *dwarf_reg = Some(*value);
+    }
+    dwarf_regs
+}
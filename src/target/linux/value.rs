@@ -0,0 +1,453 @@
+use std::convert::TryInto;
+
+use super::{Frame, LinuxTarget, PieceLocation};
+use crate::symbol::{
+    BaseEncoding, Dwarf, Field, StructType, Type, TypeId, TypeKind, Variable, VariantPart,
+};
+
+/// The maximum number of typedefs and qualifiers which are resolved while decoding a value,
+/// which guards against cycles in malformed debug info.
+const MAX_TYPE_ALIASES: usize = 16;
+
+/// The names and values of the fields of a struct, union or enum variant.
+type Fields = Vec<(Option<String>, Value)>;
+
+/// A value of a variable decoded according to its type, see `LinuxTarget::read_value`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Value {
+    /// The type of the value.
+    pub type_id: TypeId,
+    /// The name of the type, e.g. `u32` or `Option<&u8>`.
+    pub type_name: Option<String>,
+    /// The address of the value in the memory of the debuggee. `None` if the value isn't
+    /// stored in memory, e.g. because it lives in registers.
+    pub address: Option<usize>,
+    pub kind: ValueKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValueKind {
+    Bool(bool),
+    /// A signed integer of any size.
+    Int(i128),
+    /// An unsigned integer of any size.
+    UInt(u128),
+    Float(f64),
+    Char(char),
+    /// A struct along with the values of its fields, in the order of the debug info.
+    /// Fields of tuple structs are named `__0`, `__1` and so on.
+    Struct(Fields),
+    /// A tuple, e.g. `(u8, bool)`.
+    Tuple(Vec<Value>),
+    /// A union along with the values of all of its fields, as it is unknown which of them is
+    /// valid.
+    Union(Fields),
+    /// A Rust enum along with the name and the fields of its active variant.
+    Enum {
+        variant: Option<String>,
+        fields: Fields,
+    },
+    /// A C-like enum along with the name of the enumerator matching its value, if any.
+    CEnum {
+        value: i128,
+        name: Option<String>,
+    },
+    /// The elements of an array. Multidimensional arrays are arrays of arrays.
+    Array(Vec<Value>),
+    /// A pointer or a reference. The value it points to isn't read until it is requested with
+    /// `LinuxTarget::read_pointee`, as pointers can form cycles.
+    Pointer {
+        address: usize,
+        pointee: Option<TypeId>,
+    },
+    /// The raw bytes of a value of a type which can't be decoded, e.g. a function.
+    Opaque(Vec<u8>),
+}
+
+impl Value {
+    /// Returns the value of the field `name` of a struct, union or the active variant of an
+    /// enum.
+    pub fn field(&self, name: &str) -> Option<&Value> {
+        match &self.kind {
+            ValueKind::Struct(fields)
+            | ValueKind::Union(fields)
+            | ValueKind::Enum { fields, .. } => fields
+                .iter()
+                .find(|(field, _)| field.as_deref() == Some(name))
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+}
+
+impl LinuxTarget {
+    /// Reads the value of a variable in the frame `frames[index]`, see `eval_variable`.
+    /// Values are decoded recursively, except for the values behind pointers.
+    pub fn read_value(
+        &self,
+        debuginfo: &Dwarf,
+        frames: &[Frame],
+        index: usize,
+        variable: &Variable,
+    ) -> Result<Value, Box<dyn std::error::Error>> {
+        let type_id = variable.type_id().ok_or("The variable has no type")?;
+        let size = type_size(&debuginfo.get_type(type_id)?)?;
+        let pieces = self.eval_variable(debuginfo, frames, index, variable)?;
+        let bytes = self.read_pieces(&frames[index], &pieces, size)?;
+        let address = match &pieces[..] {
+            [piece] if piece.size_in_bits.is_none() => match piece.location {
+                PieceLocation::Memory(address) => Some(address),
+                _ => None,
+            },
+            _ => None,
+        };
+        decode(debuginfo, type_id, &bytes, address)
+    }
+
+    /// Reads a value of the type `type_id` stored at `address`, e.g. a global variable.
+    pub fn read_value_at(
+        &self,
+        debuginfo: &Dwarf,
+        type_id: TypeId,
+        address: usize,
+    ) -> Result<Value, Box<dyn std::error::Error>> {
+        let size = type_size(&debuginfo.get_type(type_id)?)?;
+        let mut bytes = vec![0; size];
+        self.read().read_slice(&mut bytes, address).apply_all()?;
+        decode(debuginfo, type_id, &bytes, Some(address))
+    }
+
+    /// Reads the value a pointer points to.
+    pub fn read_pointee(
+        &self,
+        debuginfo: &Dwarf,
+        pointer: &Value,
+    ) -> Result<Value, Box<dyn std::error::Error>> {
+        match pointer.kind {
+            ValueKind::Pointer {
+                address,
+                pointee: Some(pointee),
+            } => self.read_value_at(debuginfo, pointee, address),
+            ValueKind::Pointer { pointee: None, .. } => {
+                Err("The type of the pointee is unknown".into())
+            }
+            _ => Err(format!("{:?} is not a pointer", pointer.type_name).into()),
+        }
+    }
+
+    /// Reads the value a trait object, e.g. a `Box<dyn Trait>` or a `&dyn Trait`, points to.
+    /// The concrete type of the value is found by the address of the vtable.
+    pub fn read_trait_object(
+        &self,
+        debuginfo: &Dwarf,
+        object: &Value,
+    ) -> Result<Value, Box<dyn std::error::Error>> {
+        let (address, vtable) = trait_object_pointers(object)
+            .ok_or_else(|| format!("{:?} is not a trait object", object.type_name))?;
+        let concrete = debuginfo
+            .get_vtable_type(vtable)
+            .ok_or_else(|| format!("The type using the vtable at {:#x} is unknown", vtable))?;
+        self.read_value_at(debuginfo, concrete, address)
+    }
+}
+
+/// Returns the data and vtable pointers of a trait object. Some versions of Rust wrap them in
+/// further structs, e.g. `Unique`.
+fn trait_object_pointers(object: &Value) -> Option<(usize, usize)> {
+    let pointer = |value: &Value| match value.kind {
+        ValueKind::Pointer { address, .. } => Some(address),
+        _ => None,
+    };
+    if let (Some(data), Some(vtable)) = (object.field("pointer"), object.field("vtable")) {
+        return Some((pointer(data)?, pointer(vtable)?));
+    }
+    match &object.kind {
+        ValueKind::Struct(fields) => fields
+            .iter()
+            .find_map(|(_, field)| trait_object_pointers(field)),
+        _ => None,
+    }
+}
+
+fn type_size(ty: &Type) -> Result<usize, Box<dyn std::error::Error>> {
+    ty.size
+        .map(|size| size as usize)
+        .ok_or_else(|| format!("The size of {:?} is unknown", ty.name).into())
+}
+
+/// Decodes a value of the type `type_id` from its bytes, which are stored at `address`.
+fn decode(
+    debuginfo: &Dwarf,
+    type_id: TypeId,
+    bytes: &[u8],
+    address: Option<usize>,
+) -> Result<Value, Box<dyn std::error::Error>> {
+    let mut type_id = type_id;
+    let mut ty = debuginfo.get_type(type_id)?;
+    for _ in 0..MAX_TYPE_ALIASES {
+        match ty.kind {
+            TypeKind::Typedef {
+                target: Some(target),
+            }
+            | TypeKind::Qualified {
+                target: Some(target),
+            } => {
+                type_id = target;
+                ty = debuginfo.get_type(target)?;
+            }
+            _ => break,
+        }
+    }
+
+    let size = ty.size.map_or(bytes.len(), |size| size as usize);
+    let bytes = bytes
+        .get(..size)
+        .ok_or_else(|| format!("Value of {:?} is truncated", ty.name))?;
+
+    let kind = match &ty.kind {
+        TypeKind::Base(encoding) => decode_base(*encoding, bytes),
+        TypeKind::Struct(StructType {
+            variant_part: Some(variant_part),
+            ..
+        }) => decode_enum(debuginfo, variant_part, bytes, address)?,
+        TypeKind::Struct(struct_type) => {
+            let fields = decode_fields(debuginfo, &struct_type.fields, bytes, address)?;
+            // Rust describes tuples as structs with fields named `__0`, `__1` and so on.
+            if ty.name.as_deref().is_some_and(|name| name.starts_with('(')) {
+                ValueKind::Tuple(fields.into_iter().map(|(_, value)| value).collect())
+            } else {
+                ValueKind::Struct(fields)
+            }
+        }
+        TypeKind::Union(union_type) => ValueKind::Union(decode_fields(
+            debuginfo,
+            &union_type.fields,
+            bytes,
+            address,
+        )?),
+        TypeKind::Enum(enum_type) => {
+            let value = if let Some(underlying) = enum_type.underlying {
+                match decode(debuginfo, underlying, bytes, address)?.kind {
+                    ValueKind::Int(value) => value,
+                    ValueKind::UInt(value) => value as i128,
+                    kind => {
+                        return Err(format!("Unexpected enum representation: {:?}", kind).into())
+                    }
+                }
+            } else {
+                signed(bytes)
+            };
+            let name = enum_type
+                .enumerators
+                .iter()
+                .find(|enumerator| enumerator.value == value)
+                .and_then(|enumerator| enumerator.name.clone());
+            ValueKind::CEnum { value, name }
+        }
+        TypeKind::Pointer { pointee, .. } => ValueKind::Pointer {
+            address: unsigned(bytes) as usize,
+            pointee: *pointee,
+        },
+        TypeKind::Array { element, counts } => {
+            let element = element.ok_or("The type of the array elements is unknown")?;
+            let counts = counts
+                .iter()
+                .map(|count| count.map(|count| count as usize))
+                .collect::<Option<Vec<_>>>()
+                .ok_or("Arrays of unknown size are not supported")?;
+            decode_array(debuginfo, element, &counts, bytes, address)?
+        }
+        TypeKind::Subroutine { .. }
+        | TypeKind::Typedef { .. }
+        | TypeKind::Qualified { .. }
+        | TypeKind::Unspecified => ValueKind::Opaque(bytes.to_vec()),
+    };
+
+    Ok(Value {
+        type_id,
+        type_name: ty.name,
+        address,
+        kind,
+    })
+}
+
+fn decode_base(encoding: BaseEncoding, bytes: &[u8]) -> ValueKind {
+    match encoding {
+        BaseEncoding::Boolean => ValueKind::Bool(bytes.iter().any(|&byte| byte != 0)),
+        BaseEncoding::Signed | BaseEncoding::SignedChar => ValueKind::Int(signed(bytes)),
+        BaseEncoding::Unsigned | BaseEncoding::UnsignedChar => ValueKind::UInt(unsigned(bytes)),
+        BaseEncoding::Float => match bytes.len() {
+            4 => ValueKind::Float(f64::from(f32::from_le_bytes(bytes.try_into().unwrap()))),
+            8 => ValueKind::Float(f64::from_le_bytes(bytes.try_into().unwrap())),
+            _ => ValueKind::Opaque(bytes.to_vec()),
+        },
+        BaseEncoding::Char => match std::char::from_u32(unsigned(bytes) as u32) {
+            Some(c) => ValueKind::Char(c),
+            None => ValueKind::UInt(unsigned(bytes)),
+        },
+        BaseEncoding::Other(_) => ValueKind::Opaque(bytes.to_vec()),
+    }
+}
+
+fn decode_fields(
+    debuginfo: &Dwarf,
+    fields: &[Field],
+    bytes: &[u8],
+    address: Option<usize>,
+) -> Result<Fields, Box<dyn std::error::Error>> {
+    fields
+        .iter()
+        .map(|field| {
+            Ok((
+                field.name.clone(),
+                decode_field(debuginfo, field, bytes, address)?,
+            ))
+        })
+        .collect()
+}
+
+fn decode_field(
+    debuginfo: &Dwarf,
+    field: &Field,
+    bytes: &[u8],
+    address: Option<usize>,
+) -> Result<Value, Box<dyn std::error::Error>> {
+    let ty = field
+        .ty
+        .ok_or_else(|| format!("The type of the field {:?} is unknown", field.name))?;
+    // Members of unions don't have an offset.
+    let offset = field.offset.unwrap_or(0) as usize;
+    let bytes = bytes
+        .get(offset..)
+        .ok_or_else(|| format!("The field {:?} is out of bounds", field.name))?;
+    decode(
+        debuginfo,
+        ty,
+        bytes,
+        address.map(|address| address + offset),
+    )
+}
+
+/// Decodes the active variant of a Rust enum.
+fn decode_enum(
+    debuginfo: &Dwarf,
+    variant_part: &VariantPart,
+    bytes: &[u8],
+    address: Option<usize>,
+) -> Result<ValueKind, Box<dyn std::error::Error>> {
+    // Negative discriminants are sign-extended when decoded, while the discriminant values of
+    // the variants aren't, so both are compared at the size of the discriminant.
+    let (discr, discr_size) = match &variant_part.discriminant {
+        Some(discriminant) => {
+            let value = decode_field(debuginfo, discriminant, bytes, address)?;
+            let size = type_size(&debuginfo.get_type(value.type_id)?)?;
+            let discr = match value.kind {
+                ValueKind::UInt(value) => value,
+                ValueKind::Int(value) => value as u128,
+                // Niche-optimized enums can store the discriminant in a pointer.
+                ValueKind::Pointer { address, .. } => address as u128,
+                kind => return Err(format!("Unexpected discriminant: {:?}", kind).into()),
+            };
+            (Some(truncate(discr, size)), size)
+        }
+        None => (None, 0),
+    };
+
+    // The default variant is active if no other variant matches the discriminant.
+    let variant = variant_part
+        .variants
+        .iter()
+        .find(|variant| {
+            variant.discr_value.is_some()
+                && variant
+                    .discr_value
+                    .map(|value| truncate(u128::from(value), discr_size))
+                    == discr
+        })
+        .or_else(|| {
+            variant_part
+                .variants
+                .iter()
+                .find(|variant| variant.discr_value.is_none())
+        })
+        .ok_or_else(|| format!("No variant matches the discriminant {:?}", discr))?;
+
+    // Each variant usually has a single field holding a struct named after the variant.
+    match &variant.fields[..] {
+        [field] => {
+            let value = decode_field(debuginfo, field, bytes, address)?;
+            let fields = match value.kind {
+                ValueKind::Struct(fields) => fields,
+                kind => vec![(None, Value { kind, ..value })],
+            };
+            Ok(ValueKind::Enum {
+                variant: field.name.clone(),
+                fields,
+            })
+        }
+        fields => Ok(ValueKind::Enum {
+            variant: None,
+            fields: decode_fields(debuginfo, fields, bytes, address)?,
+        }),
+    }
+}
+
+fn decode_array(
+    debuginfo: &Dwarf,
+    element: TypeId,
+    counts: &[usize],
+    bytes: &[u8],
+    address: Option<usize>,
+) -> Result<ValueKind, Box<dyn std::error::Error>> {
+    let (count, inner_counts) = match counts.split_first() {
+        Some(split) => split,
+        None => return Ok(decode(debuginfo, element, bytes, address)?.kind),
+    };
+    let stride = if *count == 0 { 0 } else { bytes.len() / count };
+    let elements = (0..*count)
+        .map(|index| {
+            let offset = index * stride;
+            let bytes = &bytes[offset..offset + stride];
+            let address = address.map(|address| address + offset);
+            if inner_counts.is_empty() {
+                decode(debuginfo, element, bytes, address)
+            } else {
+                // Rows of multidimensional arrays don't have a type of their own.
+                Ok(Value {
+                    type_id: element,
+                    type_name: None,
+                    address,
+                    kind: decode_array(debuginfo, element, inner_counts, bytes, address)?,
+                })
+            }
+        })
+        .collect::<Result<_, Box<dyn std::error::Error>>>()?;
+    Ok(ValueKind::Array(elements))
+}
+
+/// Keeps the lower `size` bytes of an integer.
+fn truncate(value: u128, size: usize) -> u128 {
+    if size >= 16 {
+        value
+    } else {
+        value & ((1 << (8 * size)) - 1)
+    }
+}
+
+/// Reads a little-endian signed integer of up to 16 bytes.
+fn signed(bytes: &[u8]) -> i128 {
+    let len = bytes.len().min(16);
+    if len == 0 {
+        return 0;
+    }
+    let shift = 128 - 8 * len as u32;
+    ((unsigned(bytes) << shift) as i128) >> shift
+}
+
+/// Reads a little-endian unsigned integer of up to 16 bytes.
+fn unsigned(bytes: &[u8]) -> u128 {
+    let mut buf = [0u8; 16];
+    let len = bytes.len().min(buf.len());
+    buf[..len].copy_from_slice(&bytes[..len]);
+    u128::from_le_bytes(buf)
+}
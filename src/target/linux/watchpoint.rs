@@ -0,0 +1,288 @@
+use nix::{errno::Errno, sys::ptrace, unistd::Pid};
+use std::fmt;
+
+use super::{DebugEvent, LinuxTarget};
+
+/// The number of x86_64 debug address registers, `DR0` to `DR3`.
+const DEBUG_ADDRESS_REGISTERS: usize = 4;
+
+/// The status register `DR6`, which tells which debug address register has been hit.
+const DR6: usize = 6;
+
+/// The control register `DR7`, which enables the debug address registers.
+const DR7: usize = 7;
+
+/// The resume flag in `eflags`, which suppresses instruction breakpoints for one instruction.
+const EFLAGS_RF: u64 = 1 << 16;
+
+/// The `si_code` of a `SIGTRAP` caused by a hardware breakpoint.
+pub(super) const TRAP_HWBKPT: libc::c_int = 4;
+
+/// The kind of accesses a watchpoint is triggered by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchpointMode {
+    /// The watched memory is written.
+    Write,
+    /// The watched memory is read or written.
+    ReadWrite,
+    /// An instruction at the watched address is executed.
+    Execute,
+}
+
+/// A hardware watchpoint, implemented by the debug registers of the CPU.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Watchpoint {
+    addr: usize,
+    size: usize,
+    mode: WatchpointMode,
+    // The index of the debug address register.
+    slot: usize,
+    // The watched value when the watchpoint has been set or last hit.
+    value: u64,
+}
+
+impl Watchpoint {
+    /// Returns the address of the watched memory.
+    pub fn addr(&self) -> usize {
+        self.addr
+    }
+
+    /// Returns the number of watched bytes.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns the kind of accesses the watchpoint is triggered by.
+    pub fn mode(&self) -> WatchpointMode {
+        self.mode
+    }
+
+    /// Returns the watched value when the watchpoint has been set or last hit.
+    pub fn value(&self) -> u64 {
+        self.value
+    }
+
+    /// Returns the bits of `DR7` which enable the watchpoint.
+    fn control_bits(&self) -> u64 {
+        let access = match self.mode {
+            WatchpointMode::Execute => 0b00,
+            WatchpointMode::Write => 0b01,
+            WatchpointMode::ReadWrite => 0b11,
+        };
+        let size = match self.size {
+            1 => 0b00,
+            2 => 0b01,
+            8 => 0b10,
+            _ => 0b11,
+        };
+        (1 << (2 * self.slot)) | ((access | size << 2) << (16 + 4 * self.slot))
+    }
+}
+
+/// Describes why a watchpoint operation has failed.
+#[derive(Debug)]
+pub enum WatchpointError {
+    /// A watchpoint is already set at this address.
+    AlreadySet(usize),
+    /// There is no watchpoint at this address.
+    NotFound(usize),
+    /// All debug address registers are in use.
+    NoFreeRegister,
+    /// The size is not supported, or the address is not aligned to it. Watchpoints can be 1, 2,
+    /// 4 or 8 bytes long, and execution can only be watched for a single byte.
+    InvalidRange { addr: usize, size: usize },
+    /// The debug registers or the watched memory couldn't be accessed.
+    Debuggee(Box<dyn std::error::Error>),
+}
+
+impl fmt::Display for WatchpointError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WatchpointError::AlreadySet(addr) => {
+                write!(f, "Watchpoint at 0x{:x} is already set", addr)
+            }
+            WatchpointError::NotFound(addr) => write!(f, "No watchpoint at 0x{:x}", addr),
+            WatchpointError::NoFreeRegister => write!(f, "All debug registers are in use"),
+            WatchpointError::InvalidRange { addr, size } => {
+                write!(f, "Can't watch {} bytes at 0x{:x}", size, addr)
+            }
+            WatchpointError::Debuggee(error) => {
+                write!(f, "Failed to access the debuggee: {}", error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for WatchpointError {}
+
+impl LinuxTarget {
+    /// Returns a list of all set hardware watchpoints.
+    pub fn watchpoints(&self) -> &[Watchpoint] {
+        &self.watchpoints
+    }
+
+    /// Sets a hardware watchpoint for `size` bytes at `addr` on all threads of the debuggee.
+    /// Hits are reported as `DebugEvent::WatchpointHit`. Write accesses are reported after the
+    /// memory has been written.
+    pub fn set_watchpoint(
+        &mut self,
+        addr: usize,
+        size: usize,
+        mode: WatchpointMode,
+    ) -> Result<(), WatchpointError> {
+        let valid_size = match mode {
+            WatchpointMode::Execute => size == 1,
+            _ => [1, 2, 4, 8].contains(&size),
+        };
+        if !valid_size || !addr.is_multiple_of(size) {
+            return Err(WatchpointError::InvalidRange { addr, size });
+        }
+        if self
+            .watchpoints
+            .iter()
+            .any(|watchpoint| watchpoint.addr == addr)
+        {
+            return Err(WatchpointError::AlreadySet(addr));
+        }
+        let slot = (0..DEBUG_ADDRESS_REGISTERS)
+            .find(|&slot| {
+                self.watchpoints
+                    .iter()
+                    .all(|watchpoint| watchpoint.slot != slot)
+            })
+            .ok_or(WatchpointError::NoFreeRegister)?;
+        let value = self
+            .read_watched_value(addr, size)
+            .map_err(WatchpointError::Debuggee)?;
+
+        self.watchpoints.push(Watchpoint {
+            addr,
+            size,
+            mode,
+            slot,
+            value,
+        });
+        if let Err(error) = self.with_stopped_threads(LinuxTarget::set_debug_registers) {
+            self.watchpoints.pop();
+            // Don't leave the watchpoint behind on the threads it has been set on.
+            let _ = self.with_stopped_threads(LinuxTarget::set_debug_registers);
+            return Err(WatchpointError::Debuggee(error));
+        }
+        Ok(())
+    }
+
+    /// Removes the hardware watchpoint at `addr` from all threads of the debuggee.
+    pub fn remove_watchpoint(&mut self, addr: usize) -> Result<(), WatchpointError> {
+        let index = self
+            .watchpoints
+            .iter()
+            .position(|watchpoint| watchpoint.addr == addr)
+            .ok_or(WatchpointError::NotFound(addr))?;
+        self.watchpoints.remove(index);
+        self.with_stopped_threads(LinuxTarget::set_debug_registers)
+            .map_err(WatchpointError::Debuggee)
+    }
+
+    /// Programs the debug registers of a stopped thread according to the set watchpoints.
+    /// New threads don't inherit the debug registers, so they have to be programmed as well.
+    pub(super) fn set_debug_registers(
+        &self,
+        thread: Pid,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        // Addresses can only be changed while their registers are disabled.
+        poke_debug_register(thread, DR7, 0)?;
+        let mut control = 0;
+        for watchpoint in &self.watchpoints {
+            poke_debug_register(thread, watchpoint.slot, watchpoint.addr as u64)?;
+            control |= watchpoint.control_bits();
+        }
+        poke_debug_register(thread, DR7, control)
+    }
+
+    /// Decodes a `SIGTRAP` caused by a hardware breakpoint.
+    /// Returns `None` if it has been caused by none of our watchpoints.
+    pub(super) fn watchpoint_event(
+        &mut self,
+        thread: Pid,
+    ) -> Result<Option<DebugEvent>, Box<dyn std::error::Error>> {
+        // The status register is never cleared by the CPU.
+        let status = peek_debug_register(thread, DR6)?;
+        poke_debug_register(thread, DR6, 0)?;
+        let index = match self
+            .watchpoints
+            .iter()
+            .position(|watchpoint| status & (1 << watchpoint.slot) != 0)
+        {
+            Some(index) => index,
+            None => return Ok(None),
+        };
+
+        let Watchpoint {
+            addr, size, mode, ..
+        } = self.watchpoints[index];
+        let new_value = self.read_watched_value(addr, size)?;
+        let old_value = std::mem::replace(&mut self.watchpoints[index].value, new_value);
+        if mode == WatchpointMode::Execute {
+            // The instruction hasn't been executed yet, so resuming would hit it again.
+            let mut regs = ptrace::getregs(thread)?;
+            regs.eflags |= EFLAGS_RF;
+            ptrace::setregs(thread, regs)?;
+        }
+        Ok(Some(DebugEvent::WatchpointHit {
+            addr,
+            thread,
+            old_value,
+            new_value,
+        }))
+    }
+
+    fn read_watched_value(
+        &self,
+        addr: usize,
+        size: usize,
+    ) -> Result<u64, Box<dyn std::error::Error>> {
+        let mut bytes = [0; 8];
+        self.read()
+            .read_slice(&mut bytes[..size], addr)
+            .apply_all()?;
+        Ok(u64::from_le_bytes(bytes))
+    }
+}
+
+/// Returns the offset of a debug register in the `user` area of a thread.
+fn debug_register_offset(index: usize) -> usize {
+    std::mem::offset_of!(libc::user, u_debugreg) + index * std::mem::size_of::<u64>()
+}
+
+fn peek_debug_register(thread: Pid, index: usize) -> Result<u64, Box<dyn std::error::Error>> {
+    // `-1` is a valid value, so errors can only be told apart by `errno`.
+    let value = unsafe {
+        Errno::clear();
+        libc::ptrace(
+            libc::PTRACE_PEEKUSER,
+            thread.as_raw(),
+            debug_register_offset(index),
+            0,
+        )
+    };
+    if value == -1 && Errno::last() != Errno::UnknownErrno {
+        return Err(Errno::last().into());
+    }
+    Ok(value as u64)
+}
+
+fn poke_debug_register(
+    thread: Pid,
+    index: usize,
+    value: u64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    Errno::result(unsafe {
+        libc::ptrace(
+            libc::PTRACE_POKEUSER,
+            thread.as_raw(),
+            debug_register_offset(index),
+            value,
+        )
+    })?;
+    Ok(())
+}
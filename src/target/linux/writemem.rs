@@ -0,0 +1,260 @@
+use nix::{sys::ptrace, unistd::Pid};
+use std::{fs::OpenOptions, marker::PhantomData, mem, os::unix::fs::FileExt};
+
+/// A single memory write operation.
+struct WriteOp {
+    // Remote memory location.
+    remote_base: usize,
+    // Size of the `local_ptr` buffer.
+    len: usize,
+    // Pointer to a local source buffer.
+    local_ptr: *const libc::c_void,
+}
+
+impl WriteOp {
+    /// Converts the memory write operation into a remote IoVec.
+    fn as_remote_iovec(&self) -> libc::iovec {
+        libc::iovec {
+            iov_base: self.remote_base as *mut libc::c_void,
+            iov_len: self.len,
+        }
+    }
+
+    /// Converts the memory write operation into a local IoVec.
+    fn as_local_iovec(&self) -> libc::iovec {
+        libc::iovec {
+            iov_base: self.local_ptr as *mut libc::c_void,
+            iov_len: self.len,
+        }
+    }
+
+    /// Returns the bytes of the local source buffer.
+    fn bytes(&self) -> &[u8] {
+        // Safety: `local_ptr` points to a buffer of `len` bytes, which outlives the `WriteMemory`
+        // this operation belongs to.
+        unsafe { std::slice::from_raw_parts(self.local_ptr as *const u8, self.len) }
+    }
+}
+
+/// Allows to write memory to different locations in debuggee's memory as a single operation.
+/// On Linux, this will correspond to a single system call / context switch in the common case.
+/// Writes to pages without write permissions (e.g. code pages) fall back to `/proc/pid/mem` or
+/// `PTRACE_POKEDATA`, which are slower but ignore page protections.
+pub struct WriteMemory<'a> {
+    pid: Pid,
+    write_ops: Vec<WriteOp>,
+    _marker: PhantomData<&'a ()>,
+}
+
+impl<'a> WriteMemory<'a> {
+    pub(super) fn new(pid: Pid) -> Self {
+        WriteMemory {
+            pid,
+            write_ops: Vec::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Writes a value of type `T` to debuggee's memory at location `remote_base`.
+    /// The value is read from the provided variable `val`.
+    /// You should call `apply` in order to execute the memory write operation.
+    /// The provided variable `val` can't be modified until either `apply` is called or `self` is
+    /// dropped.
+    pub fn write<T: ?Sized>(mut self, val: &'a T, remote_base: usize) -> Self {
+        self.write_ops.push(WriteOp {
+            remote_base,
+            len: mem::size_of_val(val),
+            local_ptr: val as *const T as *const libc::c_void,
+        });
+
+        self
+    }
+
+    /// Executes the memory write operation.
+    pub fn apply(self) -> Result<(), Box<dyn std::error::Error>> {
+        // Create a list of `IoVec`s and remote `IoVec`s
+        let remote_iov = self
+            .write_ops
+            .iter()
+            .map(WriteOp::as_remote_iovec)
+            .collect::<Vec<_>>();
+
+        let local_iov = self
+            .write_ops
+            .iter()
+            .map(WriteOp::as_local_iovec)
+            .collect::<Vec<_>>();
+
+        let bytes_written = unsafe {
+            // Safety: the local buffers are valid for reads of their length for the lifetime `'a`,
+            // and the remote buffers are only accessed by the kernel.
+            libc::process_vm_writev(
+                self.pid.into(),
+                local_iov.as_ptr(),
+                local_iov.len() as libc::c_ulong,
+                remote_iov.as_ptr(),
+                remote_iov.len() as libc::c_ulong,
+                0,
+            )
+        };
+
+        let mut bytes_written = if bytes_written == -1 {
+            match nix::Error::last() {
+                // The first remote location is not writable; fall back for all operations.
+                nix::Error::Sys(nix::errno::Errno::EFAULT) => 0,
+                // fixme: return a proper error type
+                err => return Err(Box::new(err)),
+            }
+        } else {
+            bytes_written as usize
+        };
+
+        // `process_vm_writev` stops at the first location it can't write to (e.g. a read-only
+        // page), so write the remaining bytes of all subsequent operations one by one.
+        for op in &self.write_ops {
+            if bytes_written >= op.len {
+                bytes_written -= op.len;
+                continue;
+            }
+            let done = bytes_written;
+            bytes_written = 0;
+            write_protected(self.pid, op.remote_base + done, &op.bytes()[done..])?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes `bytes` to `remote_base` ignoring page protections.
+/// `/proc/pid/mem` is preferred as it allows to write an arbitrary number of bytes with a single
+/// system call, but it is not always available (e.g. when procfs is not mounted).
+fn write_protected(
+    pid: Pid,
+    remote_base: usize,
+    bytes: &[u8],
+) -> Result<(), Box<dyn std::error::Error>> {
+    if let Ok(mem) = OpenOptions::new()
+        .write(true)
+        .open(format!("/proc/{}/mem", pid))
+    {
+        mem.write_all_at(bytes, remote_base as u64)?;
+        return Ok(());
+    }
+
+    // `PTRACE_POKEDATA` writes whole words, so we need to preserve the bytes surrounding
+    // the ones we want to write.
+    const WORD_SIZE: usize = mem::size_of::<libc::c_long>();
+    let mut addr = remote_base - remote_base % WORD_SIZE;
+    while addr < remote_base + bytes.len() {
+        let mut word = ptrace::read(pid, addr as ptrace::AddressType)?.to_ne_bytes();
+        for (i, byte) in word.iter_mut().enumerate() {
+            let remote_addr = addr + i;
+            if remote_addr >= remote_base && remote_addr < remote_base + bytes.len() {
+                *byte = bytes[remote_addr - remote_base];
+            }
+        }
+        // `PTRACE_POKEDATA` takes the word to write as the data pointer argument.
+        ptrace::write(
+            pid,
+            addr as ptrace::AddressType,
+            libc::c_long::from_ne_bytes(word) as *mut libc::c_void,
+        )?;
+        addr += WORD_SIZE;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WriteMemory;
+    use nix::unistd::getpid;
+
+    use std::alloc::{alloc_zeroed, dealloc, Layout};
+
+    use nix::sys::mman::{mprotect, ProtFlags};
+
+    #[test]
+    fn write_memory() {
+        let var: usize = 52;
+        let var2: u8 = 128;
+
+        let mut write_var_op: usize = 0;
+        let mut write_var2_op: u8 = 0;
+
+        WriteMemory::new(getpid())
+            .write(&var, &mut write_var_op as *mut _ as usize)
+            .write(&var2, &mut write_var2_op as *mut _ as usize)
+            .apply()
+            .expect("Failed to apply memop");
+
+        // Volatile reads prevent the compiler from assuming the values haven't changed.
+        unsafe {
+            assert_eq!(std::ptr::read_volatile(&write_var_op), var);
+            assert_eq!(std::ptr::read_volatile(&write_var2_op), var2);
+        }
+    }
+
+    const PAGE_SIZE: usize = 4096;
+
+    #[test]
+    fn write_protected_memory() {
+        let var: usize = 9921;
+
+        unsafe {
+            let layout = Layout::from_size_align(PAGE_SIZE, PAGE_SIZE).unwrap();
+            let ptr = alloc_zeroed(layout);
+
+            mprotect(
+                ptr as *mut std::ffi::c_void,
+                PAGE_SIZE,
+                ProtFlags::PROT_READ,
+            )
+            .expect("Failed to mprotect");
+
+            WriteMemory::new(getpid())
+                .write(&var, ptr as usize)
+                .apply()
+                .expect("Failed to apply memop");
+
+            assert_eq!(std::ptr::read_volatile(ptr as *const usize), var);
+
+            mprotect(
+                ptr as *mut std::ffi::c_void,
+                PAGE_SIZE,
+                ProtFlags::PROT_WRITE,
+            )
+            .expect("Failed to mprotect");
+            dealloc(ptr, layout);
+        }
+    }
+
+    #[test]
+    fn write_cross_page_memory() {
+        let var = [123u32, 456];
+
+        unsafe {
+            let layout = Layout::from_size_align(PAGE_SIZE * 2, PAGE_SIZE).unwrap();
+            let ptr = alloc_zeroed(layout);
+
+            let array_ptr = (ptr as usize + PAGE_SIZE - std::mem::size_of::<u32>()) as *mut u8;
+            let second_page_ptr = (ptr as usize + PAGE_SIZE) as *mut std::ffi::c_void;
+
+            mprotect(second_page_ptr, PAGE_SIZE, ProtFlags::PROT_READ).expect("Failed to mprotect");
+
+            WriteMemory::new(getpid())
+                .write(&var, array_ptr as usize)
+                .apply()
+                .expect("Failed to apply memop");
+
+            assert_eq!(
+                std::ptr::read_volatile(array_ptr as *const [u32; 2]),
+                [123, 456]
+            );
+
+            mprotect(second_page_ptr, PAGE_SIZE, ProtFlags::PROT_WRITE)
+                .expect("Failed to mprotect");
+            dealloc(ptr, layout);
+        }
+    }
+}
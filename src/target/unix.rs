@@ -0,0 +1,27 @@
+mod launch;
+mod readmem;
+
+use nix::{sys::ptrace, sys::wait::waitpid, unistd::Pid};
+
+pub(crate) use launch::launch;
+pub use launch::{DebuggeeStdio, LaunchOptions, Stdio};
+pub use readmem::ReadError;
+
+/// This trait defines the common behavior for all *nix targets
+pub trait UnixTarget {
+    /// Provides the Pid of the debugee process
+    fn pid(&self) -> Pid;
+
+    /// Continues execution of a debuggee.
+    fn unpause(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        ptrace::cont(self.pid(), None)?;
+        Ok(())
+    }
+}
+
+/// Attach existing process as a debugee.
+pub(crate) fn attach(pid: Pid) -> Result<(), Box<dyn std::error::Error>> {
+    ptrace::attach(pid)?;
+    let _status = waitpid(pid, None);
+    Ok(())
+}
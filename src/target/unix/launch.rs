@@ -0,0 +1,372 @@
+use nix::{
+    errno::Errno,
+    pty::openpty,
+    sys::{
+        ptrace,
+        signal::Signal,
+        wait::{waitpid, WaitStatus},
+    },
+    unistd::{chdir, dup2, execve, fork, pipe, setsid, ForkResult, Pid},
+};
+use std::{
+    ffi::{CStr, CString, OsStr, OsString},
+    fs::{File, OpenOptions},
+    io::Read,
+    os::unix::{
+        ffi::OsStrExt,
+        io::{AsRawFd, FromRawFd, RawFd},
+    },
+    path::{Path, PathBuf},
+};
+
+/// Describes what a standard stream of a debuggee is connected to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Stdio {
+    /// The stream of the debugger is inherited, unless a PTY is requested.
+    Inherit,
+    /// The stream is connected to `/dev/null`.
+    Null,
+    /// The stream is redirected to a file. Files for output streams are created or truncated.
+    File(PathBuf),
+    /// The stream is connected to a pipe, whose other end is available in [`DebuggeeStdio`].
+    Pipe,
+}
+
+/// Describes how a debuggee is launched, see `LinuxTarget::launch`.
+///
+/// ```no_run
+/// # #[cfg(target_os = "linux")]
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # use headcrab::target::{LaunchOptions, LinuxTarget, Stdio};
+/// let options = LaunchOptions::new("/bin/ls")
+///     .arg("-l")
+///     .env("LC_ALL", "C")
+///     .cwd("/tmp")
+///     .stdout(Stdio::Pipe);
+/// let target = LinuxTarget::launch(options)?;
+/// # Ok(())
+/// # }
+/// # #[cfg(not(target_os = "linux"))]
+/// # fn main() {}
+/// ```
+#[derive(Debug, Clone)]
+pub struct LaunchOptions {
+    path: PathBuf,
+    args: Vec<OsString>,
+    env: Vec<(OsString, OsString)>,
+    inherit_env: bool,
+    cwd: Option<PathBuf>,
+    stdin: Stdio,
+    stdout: Stdio,
+    stderr: Stdio,
+    pty: bool,
+    disable_aslr: bool,
+}
+
+impl LaunchOptions {
+    /// Launches the executable at `path`, which is passed as the first argument as well.
+    /// By default, the debuggee inherits the environment, the working directory and the standard
+    /// streams of the debugger.
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        LaunchOptions {
+            path: path.as_ref().to_path_buf(),
+            args: Vec::new(),
+            env: Vec::new(),
+            inherit_env: true,
+            cwd: None,
+            stdin: Stdio::Inherit,
+            stdout: Stdio::Inherit,
+            stderr: Stdio::Inherit,
+            pty: false,
+            disable_aslr: false,
+        }
+    }
+
+    /// Adds an argument.
+    pub fn arg(mut self, arg: impl AsRef<OsStr>) -> Self {
+        self.args.push(arg.as_ref().to_os_string());
+        self
+    }
+
+    /// Adds several arguments.
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.args
+            .extend(args.into_iter().map(|arg| arg.as_ref().to_os_string()));
+        self
+    }
+
+    /// Sets an environment variable, overriding the inherited value.
+    pub fn env(mut self, key: impl AsRef<OsStr>, value: impl AsRef<OsStr>) -> Self {
+        self.env
+            .push((key.as_ref().to_os_string(), value.as_ref().to_os_string()));
+        self
+    }
+
+    /// Starts from an empty environment instead of inheriting the debugger's, so that only the
+    /// variables set with `env` afterwards are passed. The environment is stored on the stack, so
+    /// this makes the addresses of the stack independent of the debugger's environment.
+    pub fn env_clear(mut self) -> Self {
+        self.env.clear();
+        self.inherit_env = false;
+        self
+    }
+
+    /// Sets the working directory.
+    pub fn cwd(mut self, dir: impl AsRef<Path>) -> Self {
+        self.cwd = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Sets what the standard input is connected to.
+    pub fn stdin(mut self, stdin: Stdio) -> Self {
+        self.stdin = stdin;
+        self
+    }
+
+    /// Sets what the standard output is connected to.
+    pub fn stdout(mut self, stdout: Stdio) -> Self {
+        self.stdout = stdout;
+        self
+    }
+
+    /// Sets what the standard error is connected to.
+    pub fn stderr(mut self, stderr: Stdio) -> Self {
+        self.stderr = stderr;
+        self
+    }
+
+    /// Runs the debuggee in a new session with a pseudo-terminal as its controlling terminal.
+    /// Standard streams which are inherited are connected to the PTY instead, whose master end
+    /// is available in [`DebuggeeStdio`].
+    pub fn pty(mut self, pty: bool) -> Self {
+        self.pty = pty;
+        self
+    }
+
+    /// Disables address space layout randomization with `personality(ADDR_NO_RANDOMIZE)`, so that
+    /// the debuggee is loaded at the same addresses each time it is launched.
+    /// This is only supported on Linux, launching fails on other systems if it is enabled.
+    pub fn disable_aslr(mut self, disable_aslr: bool) -> Self {
+        self.disable_aslr = disable_aslr;
+        self
+    }
+
+    /// Returns the environment of the debuggee as `KEY=value` strings.
+    fn environment(&self) -> Result<Vec<CString>, Box<dyn std::error::Error>> {
+        let mut env = Vec::new();
+        if self.inherit_env {
+            env.extend(
+                std::env::vars_os().filter(|(key, _)| self.env.iter().all(|(name, _)| name != key)),
+            );
+        }
+        for (index, (key, value)) in self.env.iter().enumerate() {
+            // Later values of the same variable take precedence.
+            if self.env[index + 1..].iter().all(|(name, _)| name != key) {
+                env.push((key.clone(), value.clone()));
+            }
+        }
+        env.into_iter()
+            .map(|(key, value)| {
+                let mut var = key;
+                var.push("=");
+                var.push(value);
+                cstring(&var)
+            })
+            .collect()
+    }
+}
+
+impl From<&str> for LaunchOptions {
+    fn from(path: &str) -> Self {
+        LaunchOptions::new(path)
+    }
+}
+
+/// The debugger's ends of the standard streams of a debuggee which are connected to pipes or a
+/// PTY, see [`LaunchOptions`].
+#[derive(Debug, Default)]
+pub struct DebuggeeStdio {
+    /// The writing end of the standard input pipe.
+    pub stdin: Option<File>,
+    /// The reading end of the standard output pipe.
+    pub stdout: Option<File>,
+    /// The reading end of the standard error pipe.
+    pub stderr: Option<File>,
+    /// The master end of the PTY.
+    pub pty: Option<File>,
+}
+
+/// Launch a new debuggee process.
+pub(crate) fn launch(
+    options: &LaunchOptions,
+) -> Result<(Pid, DebuggeeStdio), Box<dyn std::error::Error>> {
+    #[cfg(not(target_os = "linux"))]
+    if options.disable_aslr {
+        return Err("Disabling ASLR is not supported on this system".into());
+    }
+
+    // Everything is prepared before forking, as the child of a multi-threaded process should
+    // only call async-signal-safe functions.
+    let path = cstring(options.path.as_os_str())?;
+    let mut args = vec![path.clone()];
+    for arg in &options.args {
+        args.push(cstring(arg)?);
+    }
+    let env = options.environment()?;
+    let cwd = match &options.cwd {
+        Some(cwd) => Some(cstring(cwd.as_os_str())?),
+        None => None,
+    };
+
+    let mut stdio = DebuggeeStdio::default();
+    let pty_slave = if options.pty {
+        let pty = openpty(None, None)?;
+        let (master, slave) =
+            unsafe { (File::from_raw_fd(pty.master), File::from_raw_fd(pty.slave)) };
+        set_cloexec(master.as_raw_fd())?;
+        set_cloexec(slave.as_raw_fd())?;
+        stdio.pty = Some(master);
+        Some(slave)
+    } else {
+        None
+    };
+    // The debuggee's ends of the standard streams, along with the stream numbers.
+    let mut redirections = Vec::new();
+    for &(fd, config) in &[
+        (libc::STDIN_FILENO, &options.stdin),
+        (libc::STDOUT_FILENO, &options.stdout),
+        (libc::STDERR_FILENO, &options.stderr),
+    ] {
+        let is_input = fd == libc::STDIN_FILENO;
+        let file = match config {
+            Stdio::Inherit => match &pty_slave {
+                Some(slave) => slave.try_clone()?,
+                None => continue,
+            },
+            Stdio::Null => OpenOptions::new()
+                .read(is_input)
+                .write(!is_input)
+                .open("/dev/null")?,
+            Stdio::File(path) if is_input => File::open(path)?,
+            Stdio::File(path) => File::create(path)?,
+            Stdio::Pipe => {
+                let (read, write) = cloexec_pipe()?;
+                let (debuggee_end, debugger_end) = if is_input {
+                    (read, write)
+                } else {
+                    (write, read)
+                };
+                match fd {
+                    libc::STDIN_FILENO => stdio.stdin = Some(debugger_end),
+                    libc::STDOUT_FILENO => stdio.stdout = Some(debugger_end),
+                    _ => stdio.stderr = Some(debugger_end),
+                }
+                debuggee_end
+            }
+        };
+        redirections.push((file, fd));
+    }
+
+    let args: Vec<&CStr> = args.iter().map(CString::as_c_str).collect();
+    let env: Vec<&CStr> = env.iter().map(CString::as_c_str).collect();
+
+    // Errors in the child are reported through a pipe which is closed by a successful `execve`.
+    let (mut error_read, error_write) = cloexec_pipe()?;
+
+    // We start the debuggee by forking the parent process.
+    // The child process invokes `ptrace(2)` with the `PTRACE_TRACEME` parameter to enable debugging features for the parent.
+    // This requires a user to have a `SYS_CAP_PTRACE` permission. See `man capabilities(7)` for more information.
+    match fork()? {
+        ForkResult::Parent { child, .. } => {
+            drop(error_write);
+            let mut errno = [0; 4];
+            let failed = error_read.read_exact(&mut errno).is_ok();
+            let status = waitpid(child, None)?;
+            if failed {
+                return Err(format!(
+                    "Failed to launch {}: {}",
+                    options.path.display(),
+                    Errno::from_i32(i32::from_ne_bytes(errno)).desc()
+                )
+                .into());
+            }
+            match status {
+                // The debuggee is stopped by `execve`, as it is traced.
+                WaitStatus::Stopped(_, Signal::SIGTRAP) => Ok((child, stdio)),
+                status => Err(format!(
+                    "Unexpected status while launching {}: {:?}",
+                    options.path.display(),
+                    status
+                )
+                .into()),
+            }
+        }
+        ForkResult::Child => {
+            let res = (|| -> nix::Result<()> {
+                if let Some(slave) = &pty_slave {
+                    setsid()?;
+                    Errno::result(unsafe {
+                        libc::ioctl(slave.as_raw_fd(), libc::TIOCSCTTY as _, 0)
+                    })?;
+                }
+                for (file, fd) in &redirections {
+                    dup2(file.as_raw_fd(), *fd)?;
+                }
+                if let Some(cwd) = &cwd {
+                    chdir(cwd.as_c_str())?;
+                }
+                #[cfg(target_os = "linux")]
+                if options.disable_aslr {
+                    // Keep the other flags of the execution domain. `0xffffffff` only queries them.
+                    let persona = Errno::result(unsafe { libc::personality(0xffffffff) })?;
+                    Errno::result(unsafe {
+                        libc::personality((persona | libc::ADDR_NO_RANDOMIZE) as libc::c_ulong)
+                    })?;
+                }
+                ptrace::traceme()?;
+
+                // execve replaces the process image, so it only returns if it has failed.
+                execve(&path, &args, &env)?;
+                Ok(())
+            })();
+
+            // Returning would run the debugger in the child, so report the error and exit.
+            let errno = match res {
+                Err(err) => err.as_errno().unwrap_or(Errno::UnknownErrno),
+                Ok(()) => Errno::UnknownErrno,
+            };
+            unsafe {
+                libc::write(
+                    error_write.as_raw_fd(),
+                    (errno as i32).to_ne_bytes().as_ptr() as *const libc::c_void,
+                    4,
+                );
+                libc::_exit(127)
+            }
+        }
+    }
+}
+
+fn cstring(string: &OsStr) -> Result<CString, Box<dyn std::error::Error>> {
+    Ok(CString::new(string.as_bytes())?)
+}
+
+/// Creates a pipe whose ends are closed by `execve`. `pipe2` would do this atomically, but it is
+/// not available on macOS.
+fn cloexec_pipe() -> Result<(File, File), Box<dyn std::error::Error>> {
+    let (read, write) = pipe()?;
+    let (read, write) = unsafe { (File::from_raw_fd(read), File::from_raw_fd(write)) };
+    set_cloexec(read.as_raw_fd())?;
+    set_cloexec(write.as_raw_fd())?;
+    Ok((read, write))
+}
+
+fn set_cloexec(fd: RawFd) -> Result<(), Box<dyn std::error::Error>> {
+    use nix::fcntl::{fcntl, FcntlArg, FdFlag};
+    fcntl(fd, FcntlArg::F_SETFD(FdFlag::FD_CLOEXEC))?;
+    Ok(())
+}
@@ -0,0 +1,34 @@
+use std::fmt;
+
+/// Describes why a single read operation of a `ReadMemory` could not be completed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadError {
+    /// Only the first `bytes_read` bytes of the value have been read.
+    /// The remaining bytes of the local value are left unchanged.
+    Partial {
+        bytes_read: usize,
+        fault_address: usize,
+    },
+    /// No bytes could be read, the local value is left unchanged.
+    Failed { fault_address: usize },
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReadError::Partial {
+                bytes_read,
+                fault_address,
+            } => write!(
+                f,
+                "Partial memory read: read {} bytes, failed at address 0x{:x}",
+                bytes_read, fault_address
+            ),
+            ReadError::Failed { fault_address } => {
+                write!(f, "Failed to read memory at address 0x{:x}", fault_address)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReadError {}
@@ -34,13 +34,13 @@ fn attach_readmem() -> Result<(), Box<dyn std::error::Error>> {
             // Read pointer
             let mut ptr_addr: usize = 0;
             unsafe {
-                target.read().read(&mut ptr_addr, str_addr).apply()?;
+                target.read().read(&mut ptr_addr, str_addr).apply_all()?;
             }
 
             // Read current value
             let mut rval = [0u8; 13];
             unsafe {
-                target.read().read(&mut rval, ptr_addr).apply()?;
+                target.read().read(&mut rval, ptr_addr).apply_all()?;
             }
 
             assert_eq!(&rval, b"Hello, world!");
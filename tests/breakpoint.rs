@@ -0,0 +1,153 @@
+//! This is a simple test to set breakpoints in a child process.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::{
+    symbol::Dwarf,
+    target::{BreakpointError, DebugEvent, LinuxTarget, UnixTarget},
+};
+
+static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/breakpoint");
+
+#[cfg(target_os = "linux")]
+#[test]
+fn breakpoint_hit() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let debuginfo = Dwarf::new(BIN_PATH)?;
+    let addr = debuginfo
+        .get_symbol_address("breakpoint_target")
+        .expect("Expected function has not been found in the target binary");
+
+    let mut target = LinuxTarget::launch(BIN_PATH)?;
+    target.breakpoints().set(&[addr]).unwrap();
+
+    let mut code = 0u8;
+    unsafe {
+        target.read().read(&mut code, addr).apply_all()?;
+    }
+    assert_eq!(code, 0xcc);
+
+    // The function is called three times.
+    for _ in 0..3 {
+        target.unpause()?;
+        assert_eq!(
+            target.next_event()?,
+            DebugEvent::BreakpointHit {
+                addr,
+                thread: target.pid()
+            }
+        );
+        assert_eq!(target.read_regs()?.rip as usize, addr);
+    }
+
+    target.unpause()?;
+    assert_eq!(target.next_event()?, DebugEvent::Exited(0));
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn breakpoint_disable() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let debuginfo = Dwarf::new(BIN_PATH)?;
+    let addr = debuginfo
+        .get_symbol_address("breakpoint_target")
+        .expect("Expected function has not been found in the target binary");
+
+    let mut target = LinuxTarget::launch(BIN_PATH)?;
+    target.breakpoints().set(&[addr]).unwrap();
+    let original_byte = target
+        .breakpoints()
+        .find_by_addr(addr)
+        .unwrap()
+        .original_byte();
+
+    match target.breakpoints().set(&[addr]).unwrap_err().as_slice() {
+        [BreakpointError::AlreadySet(err_addr)] => assert_eq!(*err_addr, addr),
+        errors => panic!("Unexpected errors: {:?}", errors),
+    }
+
+    target.breakpoints().disable(&[addr]).unwrap();
+    assert!(!target
+        .breakpoints()
+        .find_by_addr(addr)
+        .unwrap()
+        .is_enabled());
+
+    let mut code = 0u8;
+    unsafe {
+        target.read().read(&mut code, addr).apply_all()?;
+    }
+    assert_eq!(code, original_byte);
+
+    target.breakpoints().enable(&[addr]).unwrap();
+    target.unpause()?;
+    assert_eq!(
+        target.next_event()?,
+        DebugEvent::BreakpointHit {
+            addr,
+            thread: target.pid()
+        }
+    );
+
+    target.breakpoints().remove(&[addr]).unwrap();
+    assert!(target.breakpoints().get_all().is_empty());
+    match target.breakpoints().remove(&[addr]).unwrap_err().as_slice() {
+        [BreakpointError::NotFound(err_addr)] => assert_eq!(*err_addr, addr),
+        errors => panic!("Unexpected errors: {:?}", errors),
+    }
+
+    // The breakpoint has been removed, so the debuggee runs to completion.
+    target.unpause()?;
+    assert_eq!(target.next_event()?, DebugEvent::Exited(0));
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn signal_at_breakpoint() -> Result<(), Box<dyn std::error::Error>> {
+    use nix::sys::signal::{kill, Signal};
+
+    test_utils::ensure_testees();
+
+    let debuginfo = Dwarf::new(BIN_PATH)?;
+    let addr = debuginfo
+        .get_symbol_address("breakpoint_target")
+        .expect("Expected function has not been found in the target binary");
+
+    let mut target = LinuxTarget::launch(BIN_PATH)?;
+    target.breakpoints().set(&[addr]).unwrap();
+    target.unpause()?;
+    let hit = DebugEvent::BreakpointHit {
+        addr,
+        thread: target.pid(),
+    };
+    assert_eq!(target.next_event()?, hit);
+
+    // The signal interrupts stepping over the breakpoint. `SIGWINCH` is ignored by default.
+    kill(target.pid(), Signal::SIGWINCH)?;
+    target.unpause()?;
+    assert_eq!(
+        target.next_event()?,
+        DebugEvent::Signal {
+            signal: Signal::SIGWINCH,
+            thread: target.pid()
+        }
+    );
+    assert_eq!(target.read_regs()?.rip as usize, addr);
+
+    // The breakpoint is stepped over once the signal has been delivered.
+    for _ in 0..2 {
+        target.unpause()?;
+        assert_eq!(target.next_event()?, hit);
+    }
+    target.unpause()?;
+    assert_eq!(target.next_event()?, DebugEvent::Exited(0));
+
+    Ok(())
+}
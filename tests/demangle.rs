@@ -0,0 +1,39 @@
+//! This is a simple test to look up symbols by their demangled names.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::symbol::Dwarf;
+
+static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/step");
+
+#[cfg(target_os = "linux")]
+#[test]
+fn demangled_symbol_address() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let debuginfo = Dwarf::new(BIN_PATH)?;
+    let addr = debuginfo
+        .get_symbol_address("step::main")
+        .expect("Expected function has not been found in the target binary");
+
+    let function = debuginfo.get_address_function(addr).unwrap();
+    let linkage_name = function.linkage_name().unwrap();
+    assert_eq!(debuginfo.get_symbol_address(linkage_name), Some(addr));
+
+    // The legacy mangling scheme appends the hash as the last path segment.
+    let hash = &linkage_name[linkage_name.len() - 18..linkage_name.len() - 1];
+    assert!(hash.starts_with('h'));
+    assert_eq!(
+        debuginfo.get_symbol_address(&format!("step::main::{}", hash)),
+        Some(addr)
+    );
+
+    assert_eq!(
+        debuginfo.get_address_symbol(addr).as_deref(),
+        Some("step::main")
+    );
+    assert_eq!(debuginfo.get_symbol_address("step::does_not_exist"), None);
+
+    Ok(())
+}
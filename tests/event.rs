@@ -0,0 +1,118 @@
+//! This is a simple test to wait for debug events of a child process.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::target::{DebugEvent, LinuxTarget, UnixTarget};
+#[cfg(target_os = "linux")]
+use nix::sys::signal::{kill, Signal};
+
+static HELLO_BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/hello");
+static LONGER_HELLO_BIN_PATH: &str =
+    concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/longer_hello");
+static THREADS_BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/threads");
+static FORK_BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/fork");
+
+#[cfg(target_os = "linux")]
+#[test]
+fn exited() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let mut target = LinuxTarget::launch(HELLO_BIN_PATH)?;
+    target.unpause()?;
+    assert_eq!(target.next_event()?, DebugEvent::Exited(0));
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn signal() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let mut target = LinuxTarget::launch(LONGER_HELLO_BIN_PATH)?;
+    target.unpause()?;
+
+    kill(target.pid(), Signal::SIGUSR1)?;
+    assert_eq!(
+        target.next_event()?,
+        DebugEvent::Signal {
+            signal: Signal::SIGUSR1,
+            thread: target.pid()
+        }
+    );
+
+    // The signal is delivered on resumption, and the default action is to terminate the process.
+    target.unpause()?;
+    assert_eq!(target.next_event()?, DebugEvent::Killed(Signal::SIGUSR1));
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn thread_created() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let mut target = LinuxTarget::launch(THREADS_BIN_PATH)?;
+    target.unpause()?;
+
+    match target.next_event()? {
+        DebugEvent::ThreadCreated(thread) => assert_ne!(thread, target.pid()),
+        event => panic!("Unexpected event: {:?}", event),
+    }
+
+    target.unpause()?;
+    assert_eq!(target.next_event()?, DebugEvent::Exited(0));
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn fork() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let mut target = LinuxTarget::launch(FORK_BIN_PATH)?;
+    target.unpause()?;
+
+    match target.next_event()? {
+        DebugEvent::Fork(child) => assert_ne!(child, target.pid()),
+        event => panic!("Unexpected event: {:?}", event),
+    }
+
+    // The child process is not traced, so the debuggee only gets notified about its exit.
+    loop {
+        target.unpause()?;
+        match target.next_event()? {
+            DebugEvent::Signal {
+                signal: Signal::SIGCHLD,
+                ..
+            } => continue,
+            event => {
+                assert_eq!(event, DebugEvent::Exited(0));
+                break;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn unrelated_child() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let mut target = LinuxTarget::launch(HELLO_BIN_PATH)?;
+    // The child has exited by the time the debugger waits for the debuggee, but its status is
+    // left to its owner.
+    let mut child = std::process::Command::new("true").spawn()?;
+    std::thread::sleep(std::time::Duration::from_millis(100));
+
+    target.unpause()?;
+    assert_eq!(target.next_event()?, DebugEvent::Exited(0));
+    assert!(child.wait()?.success());
+
+    Ok(())
+}
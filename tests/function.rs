@@ -0,0 +1,71 @@
+//! This is a simple test to look up functions described by the debug info.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::symbol::Dwarf;
+
+static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/step");
+
+#[cfg(target_os = "linux")]
+#[test]
+fn address_function() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let debuginfo = Dwarf::new(BIN_PATH)?;
+    let addr = debuginfo
+        .get_symbol_address("inner")
+        .expect("Expected function has not been found in the target binary");
+
+    let function = debuginfo
+        .get_address_function(addr + 1)
+        .expect("No function found for `inner`");
+    assert_eq!(function.name(), Some("inner"));
+    assert!(function
+        .decl_file()
+        .unwrap()
+        .ends_with("tests/testees/step.rs"));
+    assert_eq!(function.decl_line(), Some(3));
+    assert_eq!(function.ranges().len(), 1);
+    assert_eq!(function.ranges()[0].start, addr);
+
+    // The code right after a function doesn't belong to it.
+    let end = function.ranges()[0].end;
+    assert_ne!(
+        debuginfo
+            .get_address_function(end)
+            .and_then(|function| function.name().map(str::to_string))
+            .as_deref(),
+        Some("inner")
+    );
+    assert_eq!(debuginfo.get_address_function(0), None);
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn mangled_function() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let debuginfo = Dwarf::new(BIN_PATH)?;
+    // The body of `main`.
+    let addr = debuginfo.get_line_addresses("step.rs", 14)[0];
+
+    let function = debuginfo
+        .get_address_function(addr)
+        .expect("No function found for `main`");
+    assert_eq!(function.name(), Some("main"));
+    assert!(function
+        .linkage_name()
+        .unwrap()
+        .starts_with("_ZN4step4main17h"));
+    assert_eq!(function.demangled_name(), Some("step::main"));
+    assert!(function.unit_name().unwrap().contains("step"));
+    assert_eq!(
+        debuginfo.get_address_symbol(addr).as_deref(),
+        Some("step::main")
+    );
+
+    Ok(())
+}
@@ -0,0 +1,57 @@
+//! This is a simple test to resolve the chain of inlined functions at an address.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::symbol::Dwarf;
+
+static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/inline");
+
+#[cfg(target_os = "linux")]
+#[test]
+fn inline_frames() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let debuginfo = Dwarf::new(BIN_PATH)?;
+    // The body of `add_one`, which is inlined into `double_plus_one`, which in turn is inlined
+    // into `compute`.
+    let addrs = debuginfo.get_line_addresses("inline.rs", 3);
+    assert!(!addrs.is_empty());
+
+    for addr in addrs {
+        let frames = debuginfo.get_inline_frames(addr)?;
+        let frames = frames
+            .iter()
+            .map(|frame| {
+                (
+                    frame.name.as_deref(),
+                    frame.location.as_ref().map(|location| location.line),
+                    frame.inlined,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            frames,
+            [
+                (Some("add_one"), Some(3), true),
+                (Some("double_plus_one"), Some(8), true),
+                (Some("compute"), Some(14), false),
+            ]
+        );
+    }
+
+    let frames = debuginfo.get_inline_frames(debuginfo.get_symbol_address("compute").unwrap())?;
+    assert_eq!(frames.len(), 1);
+    assert_eq!(frames[0].name.as_deref(), Some("compute"));
+    assert_eq!(frames[0].location.as_ref().unwrap().line, 13);
+    assert!(frames[0]
+        .location
+        .as_ref()
+        .unwrap()
+        .file
+        .ends_with("tests/testees/inline.rs"));
+
+    assert!(debuginfo.get_inline_frames(0)?.is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,135 @@
+//! This is a simple test to launch a debuggee with arguments, environment and redirected streams.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::target::{DebugEvent, LaunchOptions, LinuxTarget, Stdio, UnixTarget};
+#[cfg(target_os = "linux")]
+use std::io::{Read, Write};
+
+static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/launch");
+
+#[cfg(target_os = "linux")]
+#[test]
+fn redirect_streams() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let stderr_path = std::env::temp_dir().join(format!("headcrab-stderr-{}", std::process::id()));
+    let options = LaunchOptions::new(BIN_PATH)
+        .arg("first")
+        .args(["second argument", ""])
+        .env("HEADCRAB_TEST", "ignored")
+        .env("HEADCRAB_TEST", "value")
+        .cwd("/")
+        .stdin(Stdio::Pipe)
+        .stdout(Stdio::Pipe)
+        .stderr(Stdio::File(stderr_path.clone()));
+    let mut target = LinuxTarget::launch(options)?;
+
+    // Closing the pipe signals the end of the input.
+    let mut stdin = target.stdio().stdin.take().unwrap();
+    stdin.write_all(b"input")?;
+    drop(stdin);
+
+    target.unpause()?;
+    assert_eq!(target.next_event()?, DebugEvent::Exited(0));
+
+    let mut output = String::new();
+    target
+        .stdio()
+        .stdout
+        .take()
+        .unwrap()
+        .read_to_string(&mut output)?;
+    assert!(output.starts_with(
+        "args: [\"first\", \"second argument\", \"\"]\n\
+         env: Some(\"value\")\n\
+         cwd: /\n\
+         stdin: \"input\"\n"
+    ));
+    assert_eq!(std::fs::read_to_string(&stderr_path)?, "done\n");
+    std::fs::remove_file(stderr_path)?;
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn pty() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let options = LaunchOptions::new(BIN_PATH).stderr(Stdio::Null).pty(true);
+    let mut target = LinuxTarget::launch(options)?;
+    assert!(target.stdio().stdin.is_none());
+
+    // `^D` at the start of a line ends the input of a terminal.
+    let mut pty = target.stdio().pty.take().unwrap();
+    pty.write_all(b"input\n\x04")?;
+
+    target.unpause()?;
+    assert_eq!(target.next_event()?, DebugEvent::Exited(0));
+
+    // Reading fails once the output has been read and the debuggee's end is closed.
+    let mut output = Vec::new();
+    let mut buffer = [0; 1024];
+    while let Ok(len @ 1..) = pty.read(&mut buffer) {
+        output.extend_from_slice(&buffer[..len]);
+    }
+    let output = String::from_utf8(output)?;
+    assert!(output.contains("stdin: \"input\\n\""), "{}", output);
+    assert!(!output.contains("done"));
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn clear_environment() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let options = LaunchOptions::new(BIN_PATH)
+        .env("IGNORED", "value")
+        .env_clear()
+        .env("HEADCRAB_TEST", "value")
+        .stdin(Stdio::Null)
+        .stdout(Stdio::Pipe)
+        .stderr(Stdio::Null);
+    let mut target = LinuxTarget::launch(options)?;
+    target.unpause()?;
+    assert_eq!(target.next_event()?, DebugEvent::Exited(0));
+
+    let mut output = String::new();
+    target
+        .stdio()
+        .stdout
+        .take()
+        .unwrap()
+        .read_to_string(&mut output)?;
+    assert!(output.contains("env: Some(\"value\")\n"), "{}", output);
+    assert!(output.ends_with("vars: 1\n"), "{}", output);
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn disable_aslr() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    // The stack contains the environment, so it has to be the same as well.
+    let launch = || {
+        LinuxTarget::launch(
+            LaunchOptions::new(BIN_PATH)
+                .env_clear()
+                .disable_aslr(true)
+                .stdin(Stdio::Null)
+                .stdout(Stdio::Null),
+        )
+    };
+    let first = launch()?;
+    let second = launch()?;
+    assert_eq!(first.read_regs()?.rsp, second.read_regs()?.rsp);
+    assert_eq!(first.memory_maps()?, second.memory_maps()?);
+
+    Ok(())
+}
@@ -0,0 +1,123 @@
+//! This is a simple test to detach from and kill a debuggee, and to report how it has terminated.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::{
+    symbol::Dwarf,
+    target::{DebugEvent, ExitStatus, LaunchOptions, LinuxTarget, Stdio, UnixTarget},
+};
+#[cfg(target_os = "linux")]
+use nix::{
+    errno::Errno,
+    sys::{
+        signal::{kill, Signal},
+        wait::{waitpid, WaitStatus},
+    },
+};
+
+static BREAKPOINT_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/breakpoint");
+static THREADS_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/threads");
+static HELLO_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/hello");
+
+#[cfg(target_os = "linux")]
+#[test]
+fn detach() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let debuginfo = Dwarf::new(BREAKPOINT_PATH)?;
+    let addr = debuginfo.get_symbol_address("breakpoint_target").unwrap();
+
+    let mut target = LinuxTarget::launch(BREAKPOINT_PATH)?;
+    let pid = target.pid();
+    target.breakpoints().set(&[addr]).unwrap();
+    target.unpause()?;
+    assert_eq!(
+        target.next_event()?,
+        DebugEvent::BreakpointHit { addr, thread: pid }
+    );
+
+    // The function is called twice more, which would be fatal if the breakpoint was left in.
+    target.detach()?;
+    assert_eq!(waitpid(pid, None)?, WaitStatus::Exited(pid, 0));
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn detach_running_threads() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let debuginfo = Dwarf::new(THREADS_PATH)?;
+    let addr = debuginfo.get_symbol_address("threads::breakpoint").unwrap();
+
+    let mut target = LinuxTarget::launch(THREADS_PATH)?;
+    let pid = target.pid();
+    target.breakpoints().set(&[addr]).unwrap();
+    target.unpause()?;
+    assert!(matches!(target.next_event()?, DebugEvent::ThreadCreated(_)));
+
+    // The main thread keeps running while the worker is stopped at the breakpoint.
+    target.unpause()?;
+    match target.next_event()? {
+        DebugEvent::BreakpointHit { thread, .. } => assert_ne!(thread, pid),
+        event => panic!("Unexpected event: {:?}", event),
+    }
+    target.detach()?;
+    assert_eq!(waitpid(pid, None)?, WaitStatus::Exited(pid, 0));
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn kill_debuggee() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let target = LinuxTarget::launch(HELLO_PATH)?;
+    let pid = target.pid();
+    assert_eq!(target.kill()?, ExitStatus::Killed(Signal::SIGKILL));
+    // The debuggee has been reaped.
+    assert_eq!(kill(pid, None), Err(nix::Error::Sys(Errno::ESRCH)));
+
+    // Launched debuggees are killed when they are dropped.
+    let target = LinuxTarget::launch(HELLO_PATH)?;
+    let pid = target.pid();
+    drop(target);
+    assert_eq!(kill(pid, None), Err(nix::Error::Sys(Errno::ESRCH)));
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn exit_status() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let mut target = LinuxTarget::launch(LaunchOptions::new(HELLO_PATH).stdout(Stdio::Null))?;
+    assert_eq!(target.exit_status(), None);
+    target.unpause()?;
+    assert_eq!(target.next_event()?, DebugEvent::Exited(0));
+    assert_eq!(target.exit_status(), Some(ExitStatus::Exited(0)));
+    assert_eq!(target.kill()?, ExitStatus::Exited(0));
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn launch_failure() {
+    test_utils::ensure_testees();
+
+    let err = LinuxTarget::launch("/nonexistent").err().unwrap();
+    assert_eq!(
+        err.to_string(),
+        "Failed to launch /nonexistent: No such file or directory"
+    );
+
+    let err = LinuxTarget::launch(LaunchOptions::new(HELLO_PATH).cwd("/nonexistent"))
+        .err()
+        .unwrap();
+    assert!(err.to_string().ends_with("No such file or directory"));
+}
@@ -0,0 +1,144 @@
+//! This is a simple test to render values with the built-in and custom pretty printers.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::{
+    symbol::Dwarf,
+    target::{
+        DebugEvent, FormatContext, LinuxTarget, UnixTarget, Value, ValueFormatter, ValueKind,
+    },
+};
+#[cfg(target_os = "linux")]
+use std::collections::HashMap;
+
+static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/pretty");
+
+/// The stopped testee, its debug info and the values of its variables.
+#[cfg(target_os = "linux")]
+type Testee = (LinuxTarget, Dwarf, HashMap<String, Value>);
+
+/// Stops the testee once all variables are initialized, and reads their values.
+#[cfg(target_os = "linux")]
+fn read_values() -> Result<Testee, Box<dyn std::error::Error>> {
+    let debuginfo = Dwarf::new(BIN_PATH)?;
+    let addr = debuginfo.get_line_addresses("pretty.rs", 43)[0];
+
+    let mut target = LinuxTarget::launch(BIN_PATH)?;
+    target.breakpoints().set(&[addr]).unwrap();
+    target.unpause()?;
+    assert_eq!(
+        target.next_event()?,
+        DebugEvent::BreakpointHit {
+            addr,
+            thread: target.pid()
+        }
+    );
+
+    let frames = target.unwind(&debuginfo)?;
+    let mut values = HashMap::new();
+    for variable in debuginfo.get_address_variables(addr)? {
+        let value = target.read_value(&debuginfo, &frames, 0, &variable)?;
+        values.insert(variable.name().unwrap().to_string(), value);
+    }
+    Ok((target, debuginfo, values))
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn std_types() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let (target, debuginfo, values) = read_values()?;
+    let formatter = ValueFormatter::new(&target, &debuginfo);
+
+    let btree: Vec<_> = (0..30).map(|i| format!("{}: {}", i, i * 2)).collect();
+    let btree = format!("{{{}}}", btree.join(", "));
+    for (name, expected) in &[
+        ("vec", "[1, 2, 3]"),
+        ("string", "\"Hello\""),
+        ("str_slice", "\"world\""),
+        ("slice", "[2, 3]"),
+        ("boxed", "5"),
+        ("rc", "6"),
+        ("arc", "\"shared\""),
+        ("some", "Some(7)"),
+        ("none", "None"),
+        ("ok", "Ok(8)"),
+        ("err", "Err(\"failed\")"),
+        ("map", "{1: 10}"),
+        ("set", "{2}"),
+        ("btree", &btree),
+        ("deque", "[1, 2, 3]"),
+        ("cell", "Cell { value: 9 }"),
+        ("ref_cell", "RefCell { value: [true] }"),
+        ("mutex", "Mutex { data: 11, poisoned: false, .. }"),
+        // Types of other crates aren't mistaken for the ones of the standard library.
+        ("shadow_cell", "Cell { value: 12, extra: true }"),
+    ] {
+        assert_eq!(formatter.format(&values[*name]), *expected);
+    }
+
+    // Collections are truncated to the maximum number of elements.
+    let formatter = ValueFormatter::new(&target, &debuginfo).max_elements(2);
+    assert_eq!(formatter.format(&values["vec"]), "[1, 2, ..]");
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn custom_printers() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let (target, debuginfo, values) = read_values()?;
+    let mut formatter = ValueFormatter::new(&target, &debuginfo);
+    let small = &values["small"];
+    assert_eq!(
+        formatter.format(small),
+        "SmallVec { len: 2, inline: [1, 2, 0, 0] }"
+    );
+
+    formatter.register(
+        "SmallVec",
+        |value: &Value, context: &FormatContext| -> Result<String, Box<dyn std::error::Error>> {
+            let len = match value.field("len").map(|len| &len.kind) {
+                Some(ValueKind::UInt(len)) => *len as usize,
+                _ => return Err("SmallVec without a length".into()),
+            };
+            match value.field("inline").map(|inline| &inline.kind) {
+                Some(ValueKind::Array(elements)) if len <= elements.len() => {
+                    Ok(context.format_elements("[", &elements[..len], len, "]"))
+                }
+                _ => Err("SmallVec without inline elements".into()),
+            }
+        },
+    );
+    assert_eq!(formatter.format(small), "[1, 2]");
+
+    // Printers which fail fall back to the previously registered ones.
+    formatter.register(
+        "Small*",
+        |_: &Value, _: &FormatContext| -> Result<String, Box<dyn std::error::Error>> {
+            Err("Unsupported layout".into())
+        },
+    );
+    assert_eq!(formatter.format(small), "[1, 2]");
+
+    // Built-in printers can be replaced, including for nested values.
+    formatter.register(
+        "Vec<bool, *>",
+        |value: &Value, context: &FormatContext| -> Result<String, Box<dyn std::error::Error>> {
+            let element = context.template_param(value, "T")?;
+            let size = context.type_size(element)?;
+            Ok(format!("Vec of {}-byte elements", size))
+        },
+    );
+    assert_eq!(
+        formatter.format(&values["ref_cell"]),
+        "RefCell { value: Vec of 1-byte elements }"
+    );
+    assert_eq!(formatter.format(&values["vec"]), "[1, 2, 3]");
+
+    Ok(())
+}
@@ -31,31 +31,21 @@ fn read_memory() -> Result<(), Box<dyn std::error::Error>> {
 
     // Test that the address of `a_function` and one byte further both resolves back to that symbol.
     assert_eq!(
-        debuginfo
-            .get_address_symbol(addr.unwrap())
-            .as_ref()
-            .map(|name| &**name),
+        debuginfo.get_address_symbol(addr.unwrap()).as_deref(),
         Some("a_function")
     );
     assert_eq!(
-        debuginfo
-            .get_address_symbol(addr.unwrap() + 1)
-            .as_ref()
-            .map(|name| &**name),
+        debuginfo.get_address_symbol(addr.unwrap() + 1).as_deref(),
         Some("a_function")
     );
 
     // Test that invalid addresses don't resolve to a symbol.
-    assert_eq!(
-        debuginfo.get_address_symbol(0).as_ref().map(|name| &**name),
-        None,
-    );
+    assert_eq!(debuginfo.get_address_symbol(0).as_deref(), None,);
 
     assert_eq!(
         debuginfo
             .get_address_symbol(0xffff_ffff_ffff_ffff)
-            .as_ref()
-            .map(|name| &**name),
+            .as_deref(),
         None,
     );
 
@@ -63,18 +53,18 @@ fn read_memory() -> Result<(), Box<dyn std::error::Error>> {
         .get_var_address("STATICVAR")
         .expect("Expected static var has not been found in the target binary");
 
-    let target = LinuxTarget::launch(BIN_PATH)?;
+    let mut target = LinuxTarget::launch(BIN_PATH)?;
 
     // Read pointer
     let mut ptr_addr: usize = 0;
     unsafe {
-        target.read().read(&mut ptr_addr, str_addr).apply()?;
+        target.read().read(&mut ptr_addr, str_addr).apply_all()?;
     }
 
     // Read current value
     let mut rval = [0u8; 13];
     unsafe {
-        target.read().read(&mut rval, ptr_addr).apply()?;
+        target.read().read(&mut rval, ptr_addr).apply_all()?;
     }
 
     assert_eq!(&rval, b"Hello, world!");
@@ -12,7 +12,7 @@ static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/hell
 fn read_regs() -> Result<(), Box<dyn std::error::Error>> {
     test_utils::ensure_testees();
 
-    let target = LinuxTarget::launch(BIN_PATH)?;
+    let mut target = LinuxTarget::launch(BIN_PATH)?;
 
     let regs = target.read_regs()?;
 
@@ -42,7 +42,9 @@ fn read_regs() -> Result<(), Box<dyn std::error::Error>> {
 
     // IF=EI: interrupt enable flag = enable interrupts
     const EFLAGS_EI: u64 = 0x0200;
-    assert_eq!(regs.eflags, EFLAGS_EI);
+    // Bit 1 is reserved and always set by the CPU, but not all kernels report it.
+    const EFLAGS_RESERVED: u64 = 0x0002;
+    assert_eq!(regs.eflags & !EFLAGS_RESERVED, EFLAGS_EI);
 
     //assert_eq!(regs.rsp, 140735406980896); // non-deterministic
     assert_eq!(regs.ss, 43);
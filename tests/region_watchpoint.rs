@@ -0,0 +1,110 @@
+//! This is a simple test to watch accesses to a large region of memory by protecting its pages.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::{
+    symbol::Dwarf,
+    target::{DebugEvent, LinuxTarget, UnixTarget, WatchpointError, WatchpointMode},
+};
+
+static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/region");
+
+/// Launches the testee, stops it before it accesses the buffer and watches a part of the buffer.
+/// Returns the target along with the address of the buffer.
+#[cfg(target_os = "linux")]
+fn watch_buffer(mode: WatchpointMode) -> Result<(LinuxTarget, usize), Box<dyn std::error::Error>> {
+    let debuginfo = Dwarf::new(BIN_PATH)?;
+    let addr = debuginfo.get_symbol_address("region::breakpoint").unwrap();
+    let buffer = debuginfo.get_symbol_address("region::BUFFER").unwrap();
+
+    let mut target = LinuxTarget::launch(BIN_PATH)?;
+    target.breakpoints().set(&[addr]).unwrap();
+    target.unpause()?;
+    assert_eq!(
+        target.next_event()?,
+        DebugEvent::BreakpointHit {
+            addr,
+            thread: target.pid()
+        }
+    );
+    target.set_region_watchpoint(buffer + 8 * 1200..buffer + 8 * 2000, mode)?;
+    Ok((target, buffer))
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn write_accesses() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let (mut target, buffer) = watch_buffer(WatchpointMode::Write)?;
+
+    // Writes outside of the region are executed without being reported, even if they are on the
+    // same page.
+    target.unpause()?;
+    assert_eq!(
+        target.next_event()?,
+        DebugEvent::RegionAccess {
+            region: buffer + 8 * 1200,
+            addr: buffer + 8 * 1500,
+            thread: target.pid()
+        }
+    );
+    let mut value = 0u64;
+    unsafe {
+        target
+            .read()
+            .read(&mut value, buffer + 8 * 1500)
+            .apply_all()?;
+    }
+    assert_eq!(value, 2);
+
+    target.unpause()?;
+    assert_eq!(target.next_event()?, DebugEvent::Exited(0));
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn read_write_accesses() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let (mut target, buffer) = watch_buffer(WatchpointMode::ReadWrite)?;
+    for index in &[1500, 1600] {
+        target.unpause()?;
+        assert_eq!(
+            target.next_event()?,
+            DebugEvent::RegionAccess {
+                region: buffer + 8 * 1200,
+                addr: buffer + 8 * index,
+                thread: target.pid()
+            }
+        );
+    }
+
+    target.unpause()?;
+    assert_eq!(target.next_event()?, DebugEvent::Exited(0));
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn remove_region_watchpoint() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let (mut target, buffer) = watch_buffer(WatchpointMode::ReadWrite)?;
+    match target.set_region_watchpoint(buffer + 8 * 1900..buffer + 8 * 2100, WatchpointMode::Write)
+    {
+        Err(WatchpointError::AlreadySet(addr)) => assert_eq!(addr, buffer + 8 * 1200),
+        res => panic!("Unexpected result: {:?}", res),
+    }
+
+    target.remove_region_watchpoint(buffer + 8 * 1200)?;
+    assert!(target.region_watchpoints().is_empty());
+    target.unpause()?;
+    assert_eq!(target.next_event()?, DebugEvent::Exited(0));
+
+    Ok(())
+}
@@ -0,0 +1,85 @@
+//! This is a simple test to map addresses to source lines and back.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::{
+    symbol::Dwarf,
+    target::{DebugEvent, LinuxTarget, UnixTarget},
+};
+
+static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/step");
+
+#[cfg(target_os = "linux")]
+#[test]
+fn address_location() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let debuginfo = Dwarf::new(BIN_PATH)?;
+    let addr = debuginfo
+        .get_symbol_address("inner")
+        .expect("Expected function has not been found in the target binary");
+
+    // The first instruction of a function belongs to the line of its signature.
+    let location = debuginfo
+        .get_address_location(addr)
+        .expect("No source location for `inner`");
+    assert!(
+        location.file.ends_with("tests/testees/step.rs"),
+        "Unexpected file: {}",
+        location.file
+    );
+    assert_eq!(location.line, 3);
+
+    assert_eq!(debuginfo.get_address_location(0), None);
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn line_addresses() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let debuginfo = Dwarf::new(BIN_PATH)?;
+
+    // The body of `inner`.
+    let addrs = debuginfo.get_line_addresses("step.rs", 4);
+    assert!(!addrs.is_empty());
+    for &addr in &addrs {
+        assert_eq!(debuginfo.get_address_location(addr).unwrap().line, 4);
+        assert_eq!(debuginfo.get_address_symbol(addr).as_deref(), Some("inner"));
+    }
+    assert_eq!(
+        debuginfo.get_line_addresses("tests/testees/step.rs", 4),
+        addrs
+    );
+
+    assert!(debuginfo.get_line_addresses("other.rs", 4).is_empty());
+    assert!(debuginfo.get_line_addresses("step.rs", 1000).is_empty());
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn line_breakpoint() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let debuginfo = Dwarf::new(BIN_PATH)?;
+    let addrs = debuginfo.get_line_addresses("step.rs", 4);
+
+    let mut target = LinuxTarget::launch(BIN_PATH)?;
+    target.breakpoints().set(&addrs).unwrap();
+    target.unpause()?;
+    match target.next_event()? {
+        DebugEvent::BreakpointHit { addr, .. } => assert!(addrs.contains(&addr)),
+        event => panic!("Unexpected event: {:?}", event),
+    }
+
+    target.breakpoints().remove(&addrs).unwrap();
+    target.unpause()?;
+    assert_eq!(target.next_event()?, DebugEvent::Exited(0));
+
+    Ok(())
+}
@@ -0,0 +1,269 @@
+//! This is a simple test to step through the code of a child process.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::{
+    symbol::Dwarf,
+    target::{DebugEvent, LinuxTarget, UnixTarget},
+};
+
+static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/step");
+// The same testee built without frame pointers.
+static NOFP_BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/step_nofp");
+// The same testee built as a position-independent executable without frame pointers.
+static PIE_BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/step_pie");
+
+/// Launches the debuggee and runs it until the start of the given function.
+#[cfg(target_os = "linux")]
+fn run_to_function(
+    debuginfo: &Dwarf,
+    name: &str,
+) -> Result<LinuxTarget, Box<dyn std::error::Error>> {
+    launch_to_function(BIN_PATH, debuginfo, name)
+}
+
+/// Launches the debuggee at `path` and runs it until the start of the given function.
+#[cfg(target_os = "linux")]
+fn launch_to_function(
+    path: &str,
+    debuginfo: &Dwarf,
+    name: &str,
+) -> Result<LinuxTarget, Box<dyn std::error::Error>> {
+    let mut target = LinuxTarget::launch(path)?;
+    let addr = debuginfo
+        .get_symbol_address(name)
+        .expect("Expected function has not been found in the target binary")
+        + target.load_bias(debuginfo)?;
+    target.breakpoints().set(&[addr]).unwrap();
+    target.unpause()?;
+    assert_eq!(
+        target.next_event()?,
+        DebugEvent::BreakpointHit {
+            addr,
+            thread: target.pid()
+        }
+    );
+    Ok(target)
+}
+
+#[cfg(target_os = "linux")]
+fn current_function(
+    target: &LinuxTarget,
+    debuginfo: &Dwarf,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let pc = target.read_regs()?.rip as usize - target.load_bias(debuginfo)?;
+    Ok(debuginfo.get_address_symbol(pc))
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn step() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let debuginfo = Dwarf::new(BIN_PATH)?;
+    let mut target = run_to_function(&debuginfo, "outer")?;
+
+    // Step until `inner` is entered.
+    for _ in 0..100 {
+        assert_eq!(
+            target.step()?,
+            DebugEvent::SingleStep {
+                thread: target.pid()
+            }
+        );
+        if current_function(&target, &debuginfo)?.as_deref() == Some("inner") {
+            target.unpause()?;
+            assert_eq!(target.next_event()?, DebugEvent::Exited(0));
+            return Ok(());
+        }
+    }
+
+    panic!("`inner` has not been entered");
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn step_over() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let debuginfo = Dwarf::new(BIN_PATH)?;
+    let mut target = run_to_function(&debuginfo, "outer")?;
+
+    // Step until `outer` returns, without ever entering `inner`.
+    loop {
+        assert_eq!(
+            target.step_over()?,
+            DebugEvent::SingleStep {
+                thread: target.pid()
+            }
+        );
+        match current_function(&target, &debuginfo)?.as_deref() {
+            Some("outer") => {}
+            Some("inner") => panic!("`inner` has been entered"),
+            _ => break,
+        }
+    }
+
+    target.unpause()?;
+    assert_eq!(target.next_event()?, DebugEvent::Exited(0));
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn step_out() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let debuginfo = Dwarf::new(BIN_PATH)?;
+    let mut target = run_to_function(&debuginfo, "inner")?;
+
+    assert_eq!(
+        target.step_out(&debuginfo)?,
+        DebugEvent::SingleStep {
+            thread: target.pid()
+        }
+    );
+    assert_eq!(
+        current_function(&target, &debuginfo)?.as_deref(),
+        Some("outer")
+    );
+
+    // Step out of `outer` from the middle of the function this time.
+    target.step()?;
+    assert_eq!(
+        target.step_out(&debuginfo)?,
+        DebugEvent::SingleStep {
+            thread: target.pid()
+        }
+    );
+    assert_ne!(
+        current_function(&target, &debuginfo)?.as_deref(),
+        Some("outer")
+    );
+
+    target.unpause()?;
+    assert_eq!(target.next_event()?, DebugEvent::Exited(0));
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn step_out_without_frame_pointers() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let debuginfo = Dwarf::new(NOFP_BIN_PATH)?;
+    let mut target = launch_to_function(NOFP_BIN_PATH, &debuginfo, "inner")?;
+
+    // `rbp` still belongs to a caller, so the return address can only be found using the call
+    // frame information, after the stack pointer has been moved.
+    target.step()?;
+    assert_eq!(
+        target.step_out(&debuginfo)?,
+        DebugEvent::SingleStep {
+            thread: target.pid()
+        }
+    );
+    assert_eq!(
+        current_function(&target, &debuginfo)?.as_deref(),
+        Some("outer")
+    );
+
+    target.unpause()?;
+    assert_eq!(target.next_event()?, DebugEvent::Exited(0));
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn step_out_position_independent() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let debuginfo = Dwarf::new(PIE_BIN_PATH)?;
+    let mut target = launch_to_function(PIE_BIN_PATH, &debuginfo, "inner")?;
+    assert_ne!(target.load_bias(&debuginfo)?, 0);
+
+    // The call frame information is only found with the addresses of the debug info.
+    target.step()?;
+    let frames = target.unwind(&debuginfo)?;
+    assert!(frames[0].cfa.is_some());
+    assert_eq!(
+        target.step_out(&debuginfo)?,
+        DebugEvent::SingleStep {
+            thread: target.pid()
+        }
+    );
+    assert_eq!(
+        current_function(&target, &debuginfo)?.as_deref(),
+        Some("outer")
+    );
+
+    target.unpause()?;
+    assert_eq!(target.next_event()?, DebugEvent::Exited(0));
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn step_line() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let debuginfo = Dwarf::new(BIN_PATH)?;
+    let mut target = run_to_function(&debuginfo, "outer")?;
+
+    // `outer` consists of the line calling `inner` and the end of the function.
+    for _ in 0..2 {
+        assert_eq!(
+            target.step_line(&debuginfo)?,
+            DebugEvent::SingleStep {
+                thread: target.pid()
+            }
+        );
+        if current_function(&target, &debuginfo)?.as_deref() == Some("inner") {
+            target.unpause()?;
+            assert_eq!(target.next_event()?, DebugEvent::Exited(0));
+            return Ok(());
+        }
+    }
+
+    panic!("`inner` has not been entered");
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn next_line() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let debuginfo = Dwarf::new(BIN_PATH)?;
+    let mut target = run_to_function(&debuginfo, "outer")?;
+
+    // Step until `outer` returns, without ever entering `inner`.
+    let mut lines = 0;
+    loop {
+        assert_eq!(
+            target.next_line(&debuginfo)?,
+            DebugEvent::SingleStep {
+                thread: target.pid()
+            }
+        );
+        match current_function(&target, &debuginfo)?.as_deref() {
+            Some("outer") => lines += 1,
+            Some("inner") => panic!("`inner` has been entered"),
+            _ => break,
+        }
+    }
+    assert!(
+        (1..=2).contains(&lines),
+        "Stopped at {} lines in `outer`",
+        lines
+    );
+
+    target.unpause()?;
+    assert_eq!(target.next_event()?, DebugEvent::Exited(0));
+
+    Ok(())
+}
@@ -13,7 +13,7 @@ static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/hell
 fn syscall() -> Result<(), Box<dyn std::error::Error>> {
     test_utils::ensure_testees();
 
-    let target = LinuxTarget::launch(BIN_PATH)?;
+    let mut target = LinuxTarget::launch(BIN_PATH)?;
 
     println!(
         "{}\n",
@@ -22,7 +22,7 @@ fn syscall() -> Result<(), Box<dyn std::error::Error>> {
 
     let addr = target
         .mmap(
-            0 as *mut _,
+            std::ptr::null_mut(),
             1 << 20,
             libc::PROT_READ | libc::PROT_WRITE,
             libc::MAP_ANONYMOUS | libc::MAP_PRIVATE,
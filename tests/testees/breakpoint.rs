@@ -0,0 +1,9 @@
+#[no_mangle]
+#[inline(never)]
+fn breakpoint_target() {}
+
+pub fn main() {
+    for _ in 0..3 {
+        breakpoint_target();
+    }
+}
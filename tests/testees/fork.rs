@@ -0,0 +1,6 @@
+use std::process::Command;
+
+pub fn main() {
+    let status = Command::new("true").status().unwrap();
+    assert!(status.success());
+}
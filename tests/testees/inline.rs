@@ -0,0 +1,19 @@
+#[inline(always)]
+fn add_one(x: u32) -> u32 {
+    x + 1
+}
+
+#[inline(always)]
+fn double_plus_one(x: u32) -> u32 {
+    add_one(x) * 2
+}
+
+#[no_mangle]
+#[inline(never)]
+fn compute(x: u32) -> u32 {
+    double_plus_one(x)
+}
+
+pub fn main() {
+    assert_eq!(compute(1), 4);
+}
@@ -0,0 +1,13 @@
+use std::io::Read;
+
+pub fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut input = String::new();
+    std::io::stdin().read_to_string(&mut input).unwrap();
+    println!("args: {:?}", args);
+    println!("env: {:?}", std::env::var("HEADCRAB_TEST").ok());
+    println!("cwd: {}", std::env::current_dir().unwrap().display());
+    println!("stdin: {:?}", input);
+    println!("vars: {}", std::env::vars().count());
+    eprintln!("done");
+}
@@ -0,0 +1,58 @@
+#![allow(unused_variables)]
+
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+
+#[inline(never)]
+fn breakpoint() {}
+
+pub fn main() {
+    let vec = vec![1u32, 2, 3];
+    let string = String::from("Hello");
+    let str_slice = "world";
+    let slice = &vec[1..];
+    let boxed = Box::new(5i64);
+    let rc = Rc::new(6u8);
+    let arc = Arc::new(String::from("shared"));
+    let some = Some(7u16);
+    let none: Option<u16> = None;
+    let ok: Result<u8, String> = Ok(8);
+    let err: Result<u8, String> = Err(String::from("failed"));
+    let mut map = HashMap::new();
+    map.insert(1u8, 10u32);
+    let mut set = HashSet::new();
+    set.insert(2u64);
+    let mut btree = BTreeMap::new();
+    for i in 0..30u32 {
+        btree.insert(i, i * 2);
+    }
+    let mut deque = VecDeque::with_capacity(4);
+    deque.push_back(3u8);
+    deque.push_front(2);
+    deque.push_front(1);
+    let cell = Cell::new(9i8);
+    let ref_cell = RefCell::new(vec![true]);
+    let mutex = Mutex::new(11u32);
+    let small = SmallVec {
+        len: 2,
+        inline: [1, 2, 0, 0],
+    };
+    let shadow_cell = shadow::Cell { value: 12, extra: true };
+    breakpoint();
+}
+
+/// A vector storing its elements inline, as an example of a type with a custom printer.
+struct SmallVec {
+    len: usize,
+    inline: [u32; 4],
+}
+
+/// A type named like one of the standard library, which is rendered by its structure.
+mod shadow {
+    pub struct Cell {
+        pub value: u8,
+        pub extra: bool,
+    }
+}
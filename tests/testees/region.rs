@@ -0,0 +1,16 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+const ZERO: AtomicU64 = AtomicU64::new(0);
+static BUFFER: [AtomicU64; 4096] = [ZERO; 4096];
+
+#[inline(never)]
+fn breakpoint() {}
+
+pub fn main() {
+    breakpoint();
+    BUFFER[1000].store(1, Ordering::SeqCst);
+    BUFFER[1500].store(2, Ordering::SeqCst);
+    let value = BUFFER[1600].load(Ordering::SeqCst);
+    BUFFER[3000].store(value + 3, Ordering::SeqCst);
+    assert_eq!(BUFFER[3000].load(Ordering::SeqCst), 3);
+}
@@ -0,0 +1,15 @@
+#[no_mangle]
+#[inline(never)]
+fn inner(x: u32) -> u32 {
+    x + 1
+}
+
+#[no_mangle]
+#[inline(never)]
+fn outer(x: u32) -> u32 {
+    inner(x) * 2
+}
+
+pub fn main() {
+    assert_eq!(outer(1), 4);
+}
@@ -0,0 +1,15 @@
+use std::{thread, time};
+
+#[inline(never)]
+fn breakpoint() {}
+
+pub fn main() {
+    let handle = thread::Builder::new()
+        .name("worker".to_string())
+        .spawn(|| {
+            breakpoint();
+            thread::sleep(time::Duration::from_millis(100))
+        })
+        .unwrap();
+    handle.join().unwrap();
+}
@@ -0,0 +1,34 @@
+trait Shape {
+    fn area(&self) -> f64;
+}
+
+struct Circle {
+    radius: f64,
+}
+
+struct Square {
+    side: u32,
+}
+
+impl Shape for Circle {
+    fn area(&self) -> f64 {
+        3.0 * self.radius * self.radius
+    }
+}
+
+impl Shape for Square {
+    fn area(&self) -> f64 {
+        f64::from(self.side * self.side)
+    }
+}
+
+#[inline(never)]
+fn breakpoint() {}
+
+pub fn main() {
+    let boxed: Box<dyn Shape> = Box::new(Circle { radius: 1.5 });
+    let square = Square { side: 3 };
+    let reference: &dyn Shape = &square;
+    breakpoint();
+    assert!(boxed.area() + reference.area() > 0.0);
+}
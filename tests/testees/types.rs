@@ -0,0 +1,39 @@
+#![allow(dead_code)]
+
+struct Point {
+    x: i32,
+    y: i64,
+}
+
+enum Shape {
+    Circle(f64),
+    Rect { w: u32, h: u32 },
+    Empty,
+}
+
+#[no_mangle]
+#[inline(never)]
+fn inspect(point: &Point, shape: &Shape, maybe: Option<&u8>, array: [u16; 3], tuple: (u8, bool)) {
+    let sum = point.x as i64 + point.y + array[0] as i64 + tuple.0 as i64;
+    assert!(sum != 0 && maybe.is_some() && !matches!(shape, Shape::Empty));
+}
+
+pub fn main() {
+    let byte = 7u8;
+    inspect(
+        &Point { x: 1, y: -2 },
+        &Shape::Rect { w: 3, h: 4 },
+        Some(&byte),
+        [5, 6, 7],
+        (8, true),
+    );
+}
+
+#[repr(i8)]
+enum Level {
+    Low(u8) = -1,
+    High(u8) = 1,
+}
+
+#[used]
+static LEVEL: Level = Level::Low(2);
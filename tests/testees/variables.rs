@@ -0,0 +1,14 @@
+#[no_mangle]
+#[inline(never)]
+fn scopes(x: u32) -> u32 {
+    let a = x + 1;
+    let b = {
+        let c = a * 2;
+        c + 1
+    };
+    a + b
+}
+
+pub fn main() {
+    assert_eq!(scopes(1), 7);
+}
@@ -0,0 +1,21 @@
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    thread,
+};
+
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+#[inline(never)]
+fn breakpoint() {}
+
+#[inline(never)]
+fn increment() {
+    COUNTER.fetch_add(1, Ordering::SeqCst);
+}
+
+pub fn main() {
+    breakpoint();
+    increment();
+    thread::spawn(increment).join().unwrap();
+    assert_eq!(COUNTER.load(Ordering::SeqCst), 2);
+}
@@ -0,0 +1,98 @@
+//! This is a simple test to list the threads of a debuggee and control them individually.
+
+use nix::unistd::{execv, fork, ForkResult};
+use std::ffi::CString;
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::{
+    symbol::Dwarf,
+    target::{DebugEvent, LinuxTarget, ThreadState, UnixTarget},
+};
+
+static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/threads");
+
+#[cfg(target_os = "linux")]
+#[test]
+fn thread_registers() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let debuginfo = Dwarf::new(BIN_PATH)?;
+    let addr = debuginfo.get_symbol_address("threads::breakpoint").unwrap();
+
+    let mut target = LinuxTarget::launch(BIN_PATH)?;
+    target.breakpoints().set(&[addr]).unwrap();
+    target.unpause()?;
+    let worker = match target.next_event()? {
+        DebugEvent::ThreadCreated(thread) => thread,
+        event => panic!("Unexpected event: {:?}", event),
+    };
+    target.unpause()?;
+    assert_eq!(
+        target.next_event()?,
+        DebugEvent::BreakpointHit {
+            addr,
+            thread: worker
+        }
+    );
+
+    let threads = target.threads()?;
+    let names: Vec<_> = threads
+        .iter()
+        .map(|thread| (thread.tid, thread.name.as_str()))
+        .collect();
+    assert_eq!(names, [(target.pid(), "threads"), (worker, "worker")]);
+    assert_eq!(threads[1].state, ThreadState::TracingStop);
+
+    let regs = target.read_thread_regs(worker)?;
+    assert_eq!(regs.rip as usize, addr);
+    target.write_thread_regs(worker, regs)?;
+
+    assert_eq!(
+        target.step_thread(worker)?,
+        DebugEvent::SingleStep { thread: worker }
+    );
+    assert_ne!(target.read_thread_regs(worker)?.rip as usize, addr);
+
+    target.unpause()?;
+    assert_eq!(target.next_event()?, DebugEvent::Exited(0));
+
+    Ok(())
+}
+
+// Ignoring because most linux distributions have attaching to a running process disabled.
+// To run the test it either requires root privilages or CAP_SYS_PTRACE capability.
+#[ignore]
+#[cfg(target_os = "linux")]
+#[test]
+fn attach_threads() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    match fork()? {
+        ForkResult::Parent { child, .. } => {
+            use std::{thread, time};
+            thread::sleep(time::Duration::from_millis(50));
+
+            let mut target = LinuxTarget::attach(child)?;
+
+            // The worker thread has been created before attaching, and keeps running.
+            let threads = target.threads()?;
+            assert_eq!(threads.len(), 2);
+            assert_eq!(threads[0].state, ThreadState::TracingStop);
+            assert_eq!(threads[1].name, "worker");
+
+            target.unpause()?;
+            assert_eq!(target.next_event()?, DebugEvent::Exited(0));
+
+            Ok(())
+        }
+        ForkResult::Child => {
+            let path = CString::new(BIN_PATH)?;
+            execv(&path, &[])?;
+
+            // execv replaces the process image, so this place in code will not be reached.
+            unreachable!();
+        }
+    }
+}
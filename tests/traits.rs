@@ -0,0 +1,62 @@
+//! This is a simple test to read the values behind trait objects by their concrete types.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::{
+    symbol::Dwarf,
+    target::{DebugEvent, LinuxTarget, UnixTarget, ValueFormatter, ValueKind},
+};
+#[cfg(target_os = "linux")]
+use std::collections::HashMap;
+
+static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/traits");
+
+#[cfg(target_os = "linux")]
+#[test]
+fn trait_objects() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let debuginfo = Dwarf::new(BIN_PATH)?;
+    let addr = debuginfo.get_line_addresses("traits.rs", 32)[0];
+
+    let mut target = LinuxTarget::launch(BIN_PATH)?;
+    target.breakpoints().set(&[addr]).unwrap();
+    target.unpause()?;
+    assert_eq!(
+        target.next_event()?,
+        DebugEvent::BreakpointHit {
+            addr,
+            thread: target.pid()
+        }
+    );
+
+    let frames = target.unwind(&debuginfo)?;
+    let mut values = HashMap::new();
+    for variable in debuginfo.get_address_variables(addr)? {
+        let value = target.read_value(&debuginfo, &frames, 0, &variable)?;
+        values.insert(variable.name().unwrap().to_string(), value);
+    }
+
+    let circle = target.read_trait_object(&debuginfo, &values["boxed"])?;
+    assert_eq!(circle.type_name.as_deref(), Some("Circle"));
+    assert_eq!(
+        circle.field("radius").map(|radius| &radius.kind),
+        Some(&ValueKind::Float(1.5))
+    );
+
+    let square = target.read_trait_object(&debuginfo, &values["reference"])?;
+    assert_eq!(square.type_name.as_deref(), Some("Square"));
+    assert_eq!(square.address, values["square"].address);
+
+    let formatter = ValueFormatter::new(&target, &debuginfo);
+    assert_eq!(formatter.format(&values["boxed"]), "Circle { radius: 1.5 }");
+    assert_eq!(formatter.format(&values["reference"]), "Square { side: 3 }");
+
+    // Values which aren't trait objects are rejected.
+    assert!(target
+        .read_trait_object(&debuginfo, &values["square"])
+        .is_err());
+
+    Ok(())
+}
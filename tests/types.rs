@@ -0,0 +1,181 @@
+//! This is a simple test to read the types of variables from the debug info.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::symbol::{BaseEncoding, Dwarf, Type, TypeId, TypeKind};
+
+static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/types");
+
+/// Returns the types of the parameters of `inspect`.
+#[cfg(target_os = "linux")]
+fn parameter_types(debuginfo: &Dwarf) -> Result<Vec<TypeId>, Box<dyn std::error::Error>> {
+    let addr = debuginfo.get_line_addresses("types.rs", 18)[0];
+    Ok(debuginfo
+        .get_address_variables(addr)?
+        .iter()
+        .filter(|variable| variable.name() != Some("sum"))
+        .map(|variable| variable.type_id().unwrap())
+        .collect())
+}
+
+#[cfg(target_os = "linux")]
+fn pointee(debuginfo: &Dwarf, ty: &Type) -> Result<Type, Box<dyn std::error::Error>> {
+    match ty.kind {
+        TypeKind::Pointer {
+            pointee: Some(pointee),
+            is_reference: false,
+        } => debuginfo.get_type(pointee),
+        _ => panic!("Expected a pointer, found {:?}", ty),
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn struct_and_array_types() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let debuginfo = Dwarf::new(BIN_PATH)?;
+    let types = parameter_types(&debuginfo)?;
+    assert_eq!(types.len(), 5);
+
+    let point_ref = debuginfo.get_type(types[0])?;
+    assert_eq!(point_ref.name.as_deref(), Some("&types::Point"));
+    let point = pointee(&debuginfo, &point_ref)?;
+    assert_eq!(point.name.as_deref(), Some("Point"));
+    assert_eq!(point.size, Some(16));
+    let fields = match &point.kind {
+        TypeKind::Struct(point) => &point.fields,
+        kind => panic!("Expected a struct, found {:?}", kind),
+    };
+    let mut fields: Vec<_> = fields
+        .iter()
+        .map(|field| -> Result<_, Box<dyn std::error::Error>> {
+            let ty = debuginfo.get_type(field.ty.unwrap())?;
+            Ok((field.name.clone().unwrap(), field.offset, ty.name, ty.kind))
+        })
+        .collect::<Result<_, _>>()?;
+    fields.sort_by(|a, b| a.0.cmp(&b.0));
+    // Fields are reordered by the compiler.
+    assert_eq!(
+        fields,
+        [
+            (
+                "x".to_string(),
+                Some(8),
+                Some("i32".to_string()),
+                TypeKind::Base(BaseEncoding::Signed)
+            ),
+            (
+                "y".to_string(),
+                Some(0),
+                Some("i64".to_string()),
+                TypeKind::Base(BaseEncoding::Signed)
+            ),
+        ]
+    );
+
+    let array = debuginfo.get_type(types[3])?;
+    assert_eq!(array.size, Some(6));
+    match array.kind {
+        TypeKind::Array {
+            element: Some(element),
+            counts,
+        } => {
+            assert_eq!(counts, [Some(3)]);
+            assert_eq!(debuginfo.get_type(element)?.name.as_deref(), Some("u16"));
+        }
+        kind => panic!("Expected an array, found {:?}", kind),
+    }
+
+    let tuple = debuginfo.get_type(types[4])?;
+    assert_eq!(tuple.name.as_deref(), Some("(u8, bool)"));
+    match tuple.kind {
+        TypeKind::Struct(tuple) => {
+            let names: Vec<_> = tuple
+                .fields
+                .iter()
+                .map(|field| field.name.as_deref().unwrap())
+                .collect();
+            assert_eq!(names, ["__0", "__1"]);
+            assert_eq!(
+                debuginfo.get_type(tuple.fields[1].ty.unwrap())?.kind,
+                TypeKind::Base(BaseEncoding::Boolean)
+            );
+        }
+        kind => panic!("Expected a tuple, found {:?}", kind),
+    }
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn enum_types() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let debuginfo = Dwarf::new(BIN_PATH)?;
+    let types = parameter_types(&debuginfo)?;
+
+    let shape = pointee(&debuginfo, &debuginfo.get_type(types[1])?)?;
+    assert_eq!(shape.name.as_deref(), Some("Shape"));
+    let variant_part = match shape.kind {
+        TypeKind::Struct(shape) => shape.variant_part.unwrap(),
+        kind => panic!("Expected an enum, found {:?}", kind),
+    };
+    assert!(variant_part.discriminant.unwrap().is_artificial);
+    let variants: Vec<_> = variant_part
+        .variants
+        .iter()
+        .map(|variant| (variant.discr_value, variant.fields[0].name.as_deref()))
+        .collect();
+    assert_eq!(
+        variants,
+        [
+            (Some(0), Some("Circle")),
+            (Some(1), Some("Rect")),
+            (Some(2), Some("Empty"))
+        ]
+    );
+
+    // The discriminant of `None` is stored in the niche of the reference.
+    let option = debuginfo.get_type(types[2])?;
+    assert_eq!(option.name.as_deref(), Some("Option<&u8>"));
+    let variant_part = match option.kind {
+        TypeKind::Struct(option) => option.variant_part.unwrap(),
+        kind => panic!("Expected an enum, found {:?}", kind),
+    };
+    let variants: Vec<_> = variant_part
+        .variants
+        .iter()
+        .map(|variant| (variant.discr_value, variant.fields[0].name.as_deref()))
+        .collect();
+    assert_eq!(variants, [(Some(0), Some("None")), (None, Some("Some"))]);
+
+    let some = debuginfo.get_type(variant_part.variants[1].fields[0].ty.unwrap())?;
+    match some.kind {
+        TypeKind::Struct(some) => {
+            assert_eq!(some.template_params[0].name.as_deref(), Some("T"));
+            let param = debuginfo.get_type(some.template_params[0].ty.unwrap())?;
+            assert_eq!(param.name.as_deref(), Some("&u8"));
+            assert_eq!(param.size, Some(8));
+        }
+        kind => panic!("Expected a variant, found {:?}", kind),
+    }
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn global_variable_type() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let debuginfo = Dwarf::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/hello"))?;
+    let ty = debuginfo.get_type(debuginfo.get_var_type("STATICVAR").unwrap())?;
+    assert_eq!(ty.name.as_deref(), Some("&str"));
+    assert_eq!(ty.size, Some(16));
+    assert!(matches!(ty.kind, TypeKind::Struct(_)));
+
+    Ok(())
+}
@@ -0,0 +1,127 @@
+//! This is a simple test to unwind the call stack of a child process.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::{
+    symbol::Dwarf,
+    target::{DebugEvent, Frame, LinuxTarget, UnixTarget},
+};
+
+static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/step");
+
+#[cfg(target_os = "linux")]
+fn frame_names(
+    frames: &[Frame],
+    debuginfo: &Dwarf,
+) -> Result<Vec<Option<String>>, Box<dyn std::error::Error>> {
+    let mut names = Vec::new();
+    for (index, frame) in frames.iter().enumerate() {
+        let functions = frame.symbolicate(debuginfo, index == 0)?;
+        names.push(functions.last().and_then(|function| function.name.clone()));
+    }
+    Ok(names)
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn unwind() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let debuginfo = Dwarf::new(BIN_PATH)?;
+    // The start of `inner`, where the frame pointer hasn't been set up yet, and its body.
+    let entry = debuginfo
+        .get_symbol_address("inner")
+        .expect("Expected function has not been found in the target binary");
+    let body = debuginfo.get_line_addresses("step.rs", 4)[0];
+    assert_ne!(entry, body);
+
+    let mut target = LinuxTarget::launch(BIN_PATH)?;
+    target.breakpoints().set(&[entry, body]).unwrap();
+
+    for addr in [entry, body].iter().copied() {
+        target.unpause()?;
+        assert_eq!(
+            target.next_event()?,
+            DebugEvent::BreakpointHit {
+                addr,
+                thread: target.pid()
+            }
+        );
+
+        let frames = target.unwind(&debuginfo)?;
+        assert_eq!(frames[0].pc, addr);
+        assert_eq!(
+            &frame_names(&frames, &debuginfo)?[..3],
+            [
+                Some("inner".to_string()),
+                Some("outer".to_string()),
+                Some("main".to_string())
+            ]
+        );
+
+        // The return address is stored right below the CFA of a frame, which is at the top of
+        // the stack when the function is entered.
+        let cfa = frames[0].cfa.unwrap();
+        if addr == entry {
+            assert_eq!(cfa, target.read_regs()?.rsp as usize + 8);
+        }
+        let mut return_address = 0usize;
+        unsafe {
+            target
+                .read()
+                .read(&mut return_address, cfa - 8)
+                .apply_all()?;
+        }
+        assert_eq!(frames[1].pc, return_address);
+
+        // Callers are further up the stack.
+        for frames in frames.windows(2) {
+            if let (Some(cfa), Some(caller_cfa)) = (frames[0].cfa, frames[1].cfa) {
+                assert!(cfa < caller_cfa);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn unwind_frame_pointers() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let debuginfo = Dwarf::new(BIN_PATH)?;
+    // The frame pointer of `inner` is set up once its body is reached.
+    let addr = debuginfo.get_line_addresses("step.rs", 4)[0];
+
+    let mut target = LinuxTarget::launch(BIN_PATH)?;
+    target.breakpoints().set(&[addr]).unwrap();
+    target.unpause()?;
+    assert_eq!(
+        target.next_event()?,
+        DebugEvent::BreakpointHit {
+            addr,
+            thread: target.pid()
+        }
+    );
+
+    let frames = target.unwind_frame_pointers()?;
+    assert_eq!(
+        &frame_names(&frames, &debuginfo)?[..3],
+        [
+            Some("inner".to_string()),
+            Some("outer".to_string()),
+            Some("main".to_string())
+        ]
+    );
+
+    // Both unwinders agree on the frames which maintain a frame pointer.
+    let cfi_frames = target.unwind(&debuginfo)?;
+    for (frame, cfi_frame) in frames.iter().zip(&cfi_frames).take(3) {
+        assert_eq!(frame.pc, cfi_frame.pc);
+        assert_eq!(frame.cfa, cfi_frame.cfa);
+    }
+
+    Ok(())
+}
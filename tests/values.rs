@@ -0,0 +1,158 @@
+//! This is a simple test to read the values of variables according to their types.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::{
+    symbol::Dwarf,
+    target::{DebugEvent, LinuxTarget, UnixTarget, Value, ValueKind},
+};
+
+static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/types");
+
+#[cfg(target_os = "linux")]
+fn field_kinds(value: &Value) -> Vec<(Option<&str>, &ValueKind)> {
+    match &value.kind {
+        ValueKind::Struct(fields) | ValueKind::Enum { fields, .. } => fields
+            .iter()
+            .map(|(name, value)| (name.as_deref(), &value.kind))
+            .collect(),
+        kind => panic!("Expected fields, found {:?}", kind),
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn parameter_values() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let debuginfo = Dwarf::new(BIN_PATH)?;
+    let addr = debuginfo.get_line_addresses("types.rs", 18)[0];
+
+    let mut target = LinuxTarget::launch(BIN_PATH)?;
+    target.breakpoints().set(&[addr]).unwrap();
+    target.unpause()?;
+    assert_eq!(
+        target.next_event()?,
+        DebugEvent::BreakpointHit {
+            addr,
+            thread: target.pid()
+        }
+    );
+
+    let frames = target.unwind(&debuginfo)?;
+    let mut values = Vec::new();
+    for variable in debuginfo.get_address_variables(addr)? {
+        if variable.name() != Some("sum") {
+            values.push(target.read_value(&debuginfo, &frames, 0, &variable)?);
+        }
+    }
+    // Parameters of unoptimized code live on the stack.
+    assert!(values.iter().all(|value| value.address.is_some()));
+
+    let point = target.read_pointee(&debuginfo, &values[0])?;
+    assert_eq!(point.type_name.as_deref(), Some("Point"));
+    let mut fields = field_kinds(&point);
+    fields.sort_by_key(|(name, _)| *name);
+    assert_eq!(
+        fields,
+        [
+            (Some("x"), &ValueKind::Int(1)),
+            (Some("y"), &ValueKind::Int(-2))
+        ]
+    );
+    assert_eq!(
+        point.field("x").unwrap().address,
+        point.address.map(|address| address + 8)
+    );
+
+    let shape = target.read_pointee(&debuginfo, &values[1])?;
+    match &shape.kind {
+        ValueKind::Enum { variant, .. } => assert_eq!(variant.as_deref(), Some("Rect")),
+        kind => panic!("Expected an enum, found {:?}", kind),
+    }
+    assert_eq!(
+        field_kinds(&shape),
+        [
+            (Some("w"), &ValueKind::UInt(3)),
+            (Some("h"), &ValueKind::UInt(4))
+        ]
+    );
+
+    // The discriminant of `Option<&u8>` is stored in the niche of the reference.
+    let maybe = &values[2];
+    match &maybe.kind {
+        ValueKind::Enum { variant, .. } => assert_eq!(variant.as_deref(), Some("Some")),
+        kind => panic!("Expected an enum, found {:?}", kind),
+    }
+    let byte = target.read_pointee(&debuginfo, maybe.field("__0").unwrap())?;
+    assert_eq!(byte.kind, ValueKind::UInt(7));
+
+    let elements: Vec<_> = match &values[3].kind {
+        ValueKind::Array(elements) => elements.iter().map(|value| &value.kind).collect(),
+        kind => panic!("Expected an array, found {:?}", kind),
+    };
+    assert_eq!(
+        elements,
+        [
+            &ValueKind::UInt(5),
+            &ValueKind::UInt(6),
+            &ValueKind::UInt(7)
+        ]
+    );
+
+    let elements: Vec<_> = match &values[4].kind {
+        ValueKind::Tuple(elements) => elements.iter().map(|value| &value.kind).collect(),
+        kind => panic!("Expected a tuple, found {:?}", kind),
+    };
+    assert_eq!(elements, [&ValueKind::UInt(8), &ValueKind::Bool(true)]);
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn global_variable_value() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let bin_path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/hello");
+    let debuginfo = Dwarf::new(bin_path)?;
+    let target = LinuxTarget::launch(bin_path)?;
+
+    let value = target.read_value_at(
+        &debuginfo,
+        debuginfo.get_var_type("STATICVAR").unwrap(),
+        debuginfo.get_var_address("STATICVAR").unwrap(),
+    )?;
+    assert_eq!(
+        value.field("length").map(|length| &length.kind),
+        Some(&ValueKind::UInt(14))
+    );
+    let pointer = value.field("data_ptr").unwrap();
+    let first = target.read_pointee(&debuginfo, pointer)?;
+    assert_eq!(first.kind, ValueKind::UInt(u128::from(b'H')));
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn negative_discriminant() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let debuginfo = Dwarf::new(BIN_PATH)?;
+    let target = LinuxTarget::launch(BIN_PATH)?;
+
+    let level = target.read_value_at(
+        &debuginfo,
+        debuginfo.get_var_type("LEVEL").unwrap(),
+        debuginfo.get_var_address("LEVEL").unwrap(),
+    )?;
+    match &level.kind {
+        ValueKind::Enum { variant, .. } => assert_eq!(variant.as_deref(), Some("Low")),
+        kind => panic!("Expected an enum, found {:?}", kind),
+    }
+    assert_eq!(field_kinds(&level), [(Some("__0"), &ValueKind::UInt(2))]);
+
+    Ok(())
+}
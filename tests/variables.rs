@@ -0,0 +1,171 @@
+//! This is a simple test to list the variables in scope at an address.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::{
+    symbol::{Dwarf, VariableKind},
+    target::{DebugEvent, LinuxTarget, Piece, PieceLocation, UnixTarget},
+};
+
+static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/variables");
+// The same testee built as a position-independent executable without frame pointers.
+static PIE_BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/variables_pie");
+
+#[cfg(target_os = "linux")]
+fn variable_names(debuginfo: &Dwarf, line: u64) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let addr = debuginfo.get_line_addresses("variables.rs", line)[0];
+    Ok(debuginfo
+        .get_address_variables(addr)?
+        .iter()
+        .map(|variable| variable.name().unwrap().to_string())
+        .collect())
+}
+
+/// Reads the values of the variables in scope at `addr`, where the debuggee is stopped.
+#[cfg(target_os = "linux")]
+fn scope_values(
+    target: &LinuxTarget,
+    debuginfo: &Dwarf,
+    addr: usize,
+) -> Result<Vec<(String, u32)>, Box<dyn std::error::Error>> {
+    let frames = target.unwind(debuginfo)?;
+    let mut values = Vec::new();
+    for variable in debuginfo.get_address_variables(addr)? {
+        let pieces = target.eval_variable(debuginfo, &frames, 0, &variable)?;
+        // Variables of unoptimized code live on the stack.
+        assert!(matches!(pieces[0].location, PieceLocation::Memory(_)));
+        let bytes = target.read_pieces(&frames[0], &pieces, 4)?;
+        values.push((
+            variable.name().unwrap().to_string(),
+            u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        ));
+    }
+    Ok(values)
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn scope_variables() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let debuginfo = Dwarf::new(BIN_PATH)?;
+
+    // Inside of the block, `b` isn't initialized yet.
+    assert_eq!(variable_names(&debuginfo, 7)?, ["c", "a", "x"]);
+    // After the block, `c` is out of scope.
+    assert_eq!(variable_names(&debuginfo, 9)?, ["b", "a", "x"]);
+
+    let addr = debuginfo.get_line_addresses("variables.rs", 9)[0];
+    let variables = debuginfo.get_address_variables(addr)?;
+    let x = variables.last().unwrap();
+    assert_eq!(x.kind(), VariableKind::Parameter);
+    assert_eq!(x.type_name(), Some("u32"));
+    assert_eq!(x.decl_line(), Some(3));
+    assert!(x
+        .decl_file()
+        .unwrap()
+        .ends_with("tests/testees/variables.rs"));
+    assert!(x.location().unwrap().expression_at(addr).is_some());
+    assert_eq!(variables[0].kind(), VariableKind::Local);
+
+    // There are no variables outside of functions.
+    assert!(debuginfo.get_address_variables(0)?.is_empty());
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn variable_values() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let debuginfo = Dwarf::new(BIN_PATH)?;
+    let inside_block = debuginfo.get_line_addresses("variables.rs", 7)[0];
+    let after_block = debuginfo.get_line_addresses("variables.rs", 9)[0];
+
+    let mut target = LinuxTarget::launch(BIN_PATH)?;
+    target
+        .breakpoints()
+        .set(&[inside_block, after_block])
+        .unwrap();
+
+    for (addr, expected) in [
+        (inside_block, [("c", 4), ("a", 2), ("x", 1)]),
+        (after_block, [("b", 5), ("a", 2), ("x", 1)]),
+    ]
+    .iter()
+    .copied()
+    {
+        target.unpause()?;
+        assert_eq!(
+            target.next_event()?,
+            DebugEvent::BreakpointHit {
+                addr,
+                thread: target.pid()
+            }
+        );
+
+        let values = scope_values(&target, &debuginfo, addr)?;
+        let expected: Vec<_> = expected
+            .iter()
+            .map(|(name, value)| (name.to_string(), *value))
+            .collect();
+        assert_eq!(values, expected);
+    }
+
+    // A value composed of pieces in different locations.
+    let frames = target.unwind(&debuginfo)?;
+    let pieces = [
+        Piece {
+            size_in_bits: Some(16),
+            bit_offset: None,
+            location: PieceLocation::Value(vec![1]),
+        },
+        Piece {
+            size_in_bits: Some(16),
+            bit_offset: Some(8),
+            location: PieceLocation::Register(7),
+        },
+    ];
+    let rsp = target.read_regs()?.rsp.to_le_bytes();
+    assert_eq!(
+        target.read_pieces(&frames[0], &pieces, 4)?,
+        [1, 0, rsp[1], rsp[2]]
+    );
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn position_independent_variable_values() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let debuginfo = Dwarf::new(PIE_BIN_PATH)?;
+    let addr = debuginfo.get_line_addresses("variables.rs", 9)[0];
+
+    let mut target = LinuxTarget::launch(PIE_BIN_PATH)?;
+    // Addresses of the debug info have to be relocated to where the executable is loaded.
+    let bias = target.load_bias(&debuginfo)?;
+    assert_ne!(bias, 0);
+    target.breakpoints().set(&[addr + bias]).unwrap();
+    target.unpause()?;
+    assert_eq!(
+        target.next_event()?,
+        DebugEvent::BreakpointHit {
+            addr: addr + bias,
+            thread: target.pid()
+        }
+    );
+
+    // The locations and the frame bases are looked up by the addresses of the debug info.
+    let values = scope_values(&target, &debuginfo, addr)?;
+    let expected: Vec<_> = [("b", 5), ("a", 2), ("x", 1)]
+        .iter()
+        .map(|(name, value)| (name.to_string(), *value))
+        .collect();
+    assert_eq!(values, expected);
+
+    Ok(())
+}
@@ -0,0 +1,160 @@
+//! This is a simple test to watch accesses to memory with hardware watchpoints.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::{
+    symbol::Dwarf,
+    target::{DebugEvent, LinuxTarget, UnixTarget, WatchpointError, WatchpointMode},
+};
+
+static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/watch");
+
+/// Launches the testee and stops it before it accesses the counter.
+#[cfg(target_os = "linux")]
+fn launch(debuginfo: &Dwarf) -> Result<LinuxTarget, Box<dyn std::error::Error>> {
+    let addr = debuginfo.get_symbol_address("watch::breakpoint").unwrap();
+    let mut target = LinuxTarget::launch(BIN_PATH)?;
+    target.breakpoints().set(&[addr]).unwrap();
+    target.unpause()?;
+    assert_eq!(
+        target.next_event()?,
+        DebugEvent::BreakpointHit {
+            addr,
+            thread: target.pid()
+        }
+    );
+    target.breakpoints().remove(&[addr]).unwrap();
+    Ok(target)
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn write_watchpoint() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let debuginfo = Dwarf::new(BIN_PATH)?;
+    let counter = debuginfo.get_symbol_address("watch::COUNTER").unwrap();
+    let mut target = launch(&debuginfo)?;
+    target.set_watchpoint(counter, 8, WatchpointMode::Write)?;
+
+    target.unpause()?;
+    assert_eq!(
+        target.next_event()?,
+        DebugEvent::WatchpointHit {
+            addr: counter,
+            thread: target.pid(),
+            old_value: 0,
+            new_value: 1
+        }
+    );
+
+    // Watchpoints are set on threads created afterwards as well.
+    target.unpause()?;
+    let thread = match target.next_event()? {
+        DebugEvent::ThreadCreated(thread) => thread,
+        event => panic!("Unexpected event: {:?}", event),
+    };
+    target.unpause()?;
+    assert_eq!(
+        target.next_event()?,
+        DebugEvent::WatchpointHit {
+            addr: counter,
+            thread,
+            old_value: 1,
+            new_value: 2
+        }
+    );
+
+    // Reads are only reported by read/write watchpoints.
+    target.remove_watchpoint(counter)?;
+    target.set_watchpoint(counter, 8, WatchpointMode::ReadWrite)?;
+    target.unpause()?;
+    assert_eq!(
+        target.next_event()?,
+        DebugEvent::WatchpointHit {
+            addr: counter,
+            thread: target.pid(),
+            old_value: 2,
+            new_value: 2
+        }
+    );
+
+    target.remove_watchpoint(counter)?;
+    assert!(target.watchpoints().is_empty());
+    target.unpause()?;
+    assert_eq!(target.next_event()?, DebugEvent::Exited(0));
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn execute_watchpoint() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let debuginfo = Dwarf::new(BIN_PATH)?;
+    let increment = debuginfo.get_symbol_address("watch::increment").unwrap();
+    let mut target = launch(&debuginfo)?;
+    target.set_watchpoint(increment, 1, WatchpointMode::Execute)?;
+
+    // The function is called once by each thread.
+    let mut threads = Vec::new();
+    loop {
+        target.unpause()?;
+        match target.next_event()? {
+            DebugEvent::WatchpointHit { addr, thread, .. } => {
+                assert_eq!(addr, increment);
+                assert_eq!(target.read_thread_regs(thread)?.rip as usize, increment);
+                threads.push(thread);
+            }
+            DebugEvent::ThreadCreated(_) => {}
+            DebugEvent::Exited(code) => {
+                assert_eq!(code, 0);
+                break;
+            }
+            event => panic!("Unexpected event: {:?}", event),
+        }
+    }
+    assert_eq!(threads.len(), 2);
+    assert_eq!(threads[0], target.pid());
+    assert_ne!(threads[1], target.pid());
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn invalid_watchpoints() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let debuginfo = Dwarf::new(BIN_PATH)?;
+    let counter = debuginfo.get_symbol_address("watch::COUNTER").unwrap();
+    let mut target = launch(&debuginfo)?;
+
+    for &(addr, size, mode) in &[
+        (counter + 4, 8, WatchpointMode::Write),
+        (counter, 3, WatchpointMode::ReadWrite),
+        (counter, 8, WatchpointMode::Execute),
+    ] {
+        match target.set_watchpoint(addr, size, mode) {
+            Err(WatchpointError::InvalidRange { .. }) => {}
+            res => panic!("Unexpected result: {:?}", res),
+        }
+    }
+
+    // There are only four debug address registers.
+    for offset in 0..4 {
+        target.set_watchpoint(counter + offset, 1, WatchpointMode::Write)?;
+    }
+    match target.set_watchpoint(counter + 4, 1, WatchpointMode::Write) {
+        Err(WatchpointError::NoFreeRegister) => {}
+        res => panic!("Unexpected result: {:?}", res),
+    }
+    match target.set_watchpoint(counter, 1, WatchpointMode::Write) {
+        Err(WatchpointError::AlreadySet(addr)) => assert_eq!(addr, counter),
+        res => panic!("Unexpected result: {:?}", res),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,44 @@
+//! This is a simple test to write memory to a child process.
+
+mod test_utils;
+
+#[cfg(target_os = "linux")]
+use headcrab::{symbol::Dwarf, target::LinuxTarget, target::UnixTarget};
+
+static BIN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/testees/hello");
+
+// FIXME: Running this test just for linux because of privileges issue on macOS. Enable for everything after fixing.
+#[cfg(target_os = "linux")]
+#[test]
+fn write_memory() -> Result<(), Box<dyn std::error::Error>> {
+    test_utils::ensure_testees();
+
+    let debuginfo = Dwarf::new(BIN_PATH)?;
+
+    let str_addr = debuginfo
+        .get_var_address("STATICVAR")
+        .expect("Expected static var has not been found in the target binary");
+
+    let mut target = LinuxTarget::launch(BIN_PATH)?;
+
+    // Read pointer
+    let mut ptr_addr: usize = 0;
+    unsafe {
+        target.read().read(&mut ptr_addr, str_addr).apply_all()?;
+    }
+
+    // Overwrite the string contents, which are located in a read-only section.
+    target.write().write(b"Hello, crab!!", ptr_addr).apply()?;
+
+    // Read new value
+    let mut rval = [0u8; 13];
+    unsafe {
+        target.read().read(&mut rval, ptr_addr).apply_all()?;
+    }
+
+    assert_eq!(&rval, b"Hello, crab!!");
+
+    target.unpause()?;
+
+    Ok(())
+}